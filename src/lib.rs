@@ -0,0 +1,5 @@
+pub mod structure;
+pub mod tool;
+
+#[cfg(test)]
+mod test_util;
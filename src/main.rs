@@ -1,16 +1,46 @@
-mod structure;
-mod tool;
-
-use std::collections::btree_map::Keys;
-
-use crate::structure::bst::BstNode;
-use crate::structure::tree::Node;
-use crate::structure::tree::NodeLink;
-use crate::structure::bst::BstNodeLink;
-use crate::tool::generate_dotfile;
-use crate::tool::generate_dotfile_bst;
+use daa_bst2::structure::bst::BstNode;
+use daa_bst2::structure::tree::Node;
+use daa_bst2::structure::tree::NodeLink;
+use daa_bst2::structure::bst::BstNodeLink;
+use daa_bst2::tool::generate_dotfile;
+use daa_bst2::tool::generate_dotfile_bst;
+use daa_bst2::tool::bench;
+use daa_bst2::tool::differential;
+use daa_bst2::tool::dot_diff::generate_dotfile_bst_diff;
+use daa_bst2::tool::html::BstViewer;
+use daa_bst2::tool::trace;
 
 fn main() {
+    //pass "bench" (optionally followed by sizes) to run the benchmarks against BTreeSet
+    if std::env::args().nth(1).as_deref() == Some("bench") {
+        let sizes: Vec<usize> = std::env::args().skip(2).filter_map(|arg| arg.parse().ok()).collect();
+        if sizes.is_empty() {
+            bench::run_benchmarks(&bench::DEFAULT_SIZES);
+        } else {
+            bench::run_benchmarks(&sizes);
+        }
+        return;
+    }
+    //pass "costs" [size] [dir] to write per call operation costs as csv
+    if std::env::args().nth(1).as_deref() == Some("costs") {
+        let size = std::env::args().nth(2).and_then(|arg| arg.parse().ok()).unwrap_or(1000);
+        let dir = std::env::args().nth(3).unwrap_or("costs".to_string());
+        bench::run_costs(size, &dir).expect("Failed to write costs");
+        return;
+    }
+    //pass "fuzz" [first seed] [runs] [length] to compare random operation sequences against BTreeSet
+    if std::env::args().nth(1).as_deref() == Some("fuzz") {
+        let arg = |index: usize, default: u64| std::env::args().nth(index).and_then(|arg| arg.parse().ok()).unwrap_or(default);
+        if !differential::run_fuzz(arg(2, 0), arg(3, 1000), arg(4, 200) as usize) {
+            std::process::exit(1);
+        }
+        return;
+    }
+    //pass "trace" <insert|search|successor|delete> <key> [dir] to write step snapshots of the demo tree
+    if std::env::args().nth(1).as_deref() == Some("trace") {
+        run_trace();
+        return;
+    }
     let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
     rootlink.borrow_mut().add_left_child(&rootlink, 6);
     BstNode::tree_insert(&rootlink, 18);
@@ -23,31 +53,38 @@ fn main() {
     BstNode::tree_insert(&rootlink, 13);
     BstNode::tree_insert(&rootlink, 9);
     let main_tree_path = "bst_graph.dot";
-    generate_dotfile_bst(&rootlink, main_tree_path);
+    generate_dotfile_bst(&rootlink, main_tree_path).expect("Failed to write the dot file");
+    let mut viewer = BstViewer::new();
+    viewer.record("initial tree", &rootlink);
     let mut senode = rootlink.clone().borrow_mut().tree_search(&18).unwrap();
     println!("parent of 18: {:?}", senode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     // BstNode::tree_delete(&mut rootlink.clone(), &senode.clone()); //BOTH CHILD EXIST SITUATION
     let mut ti = rootlink.clone().borrow_mut().tree_search(&20);
-    println!("20 exist: {:?}", !(ti.is_none()));
+    println!("20 exist: {:?}", ti.is_some());
     let ti3 = rootlink.clone().borrow().tree_search(&18);
     println!("parent of 20: {:?}", ti.unwrap().borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
-    println!("18 exist: {:?}", !(ti3.is_none()));
+    println!("18 exist: {:?}", ti3.is_some());
     senode = rootlink.clone().borrow_mut().tree_search(&4).unwrap();
     println!("parent of 4: {:?}", senode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     BstNode::tree_delete(&mut rootlink.clone(), &senode.clone()); //BOTH CHILD NOT EXIST SITUATION
+    viewer.record("delete 4", &rootlink);
     ti = rootlink.clone().borrow().tree_search(&3);
-    println!("4 exist: {:?}", !(ti.unwrap().borrow().right.is_none()));
+    println!("4 exist: {:?}", ti.unwrap().borrow().right.is_some());
     senode = rootlink.clone().borrow_mut().tree_search(&15).unwrap();
     let sixnode = rootlink.clone().borrow_mut().tree_search(&6).unwrap();
     println!("pre parent of 6: {:?}", sixnode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     let sixnode = rootlink.clone().borrow_mut().tree_search(&20).unwrap();
     println!("pre parent of 20: {:?}", sixnode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
-    println!("parent of 15 exist: {:?}", !(senode.borrow().parent.is_none()));
+    println!("parent of 15 exist: {:?}", senode.borrow().parent.is_some());
+    //tree_delete works in place, keep a copy to diff against
+    let before_delete = BstNode::from_preorder(&BstNode::preorder_keys(&rootlink)).unwrap();
     BstNode::tree_delete(&mut rootlink.clone(), &senode.clone()); //DELETING HEAD NODE SITUATION
+    viewer.record("delete 15", &rootlink);
     let main_tree_path = "bst_graph1.dot";
-    generate_dotfile_bst(&rootlink, main_tree_path);
+    generate_dotfile_bst(&rootlink, main_tree_path).expect("Failed to write the dot file");
+    generate_dotfile_bst_diff(&before_delete, &rootlink, "bst_graph1_diff.dot").expect("Failed to write the dot file");
     let senode = rootlink.clone().borrow_mut().tree_search(&17);
-    println!("17 par exist: {:?}", !(senode.unwrap().borrow().parent.is_none()));
+    println!("17 par exist: {:?}", senode.unwrap().borrow().parent.is_some());
     let sixnode = rootlink.clone().borrow_mut().tree_search(&6).unwrap();
     println!("post parent of 6: {:?}", sixnode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     let sixnode = rootlink.clone().borrow_mut().tree_search(&20).unwrap();
@@ -57,25 +94,29 @@ fn main() {
     let ninenode = rootlink.clone().borrow_mut().tree_search(&9).unwrap();
     println!("PRE parent of 9: {:?}", ninenode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     println!("left child of 13: {:?}", thirteennode.borrow().left.as_ref().unwrap().borrow().key.unwrap());
-    println!("right child of 13 exist: {:?}", !(thirteennode.borrow().right.is_none()));
+    println!("right child of 13 exist: {:?}", thirteennode.borrow().right.is_some());
     BstNode::tree_delete(&mut rootlink.clone(), &thirteennode.clone()); //ONLY LEFT CHILD EXIST SITUATION
+    viewer.record("delete 13", &rootlink);
     let ninenode = rootlink.clone().borrow_mut().tree_search(&9).unwrap();
     println!("POST parent of 9: {:?}", ninenode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     let sevennode = rootlink.clone().borrow_mut().tree_search(&7).unwrap();
-    println!("left child of 7 exist: {:?}", !(sevennode.borrow().left.is_none()));
+    println!("left child of 7 exist: {:?}", sevennode.borrow().left.is_some());
     println!("right child of 7: {:?}", sevennode.borrow().right.as_ref().unwrap().borrow().key.unwrap());
     println!("parent of 7: {:?}", sevennode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     let ninenode = rootlink.clone().borrow_mut().tree_search(&9).unwrap();
     println!("PRE parent of 9: {:?}", ninenode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     BstNode::tree_delete(&mut rootlink.clone(), &sevennode.clone()); //ONLY RIGHT CHILD EXIST SITUATION
+    viewer.record("delete 7", &rootlink);
     let ninenode = rootlink.clone().borrow_mut().tree_search(&9).unwrap();
     println!("POST parent of 9: {:?}", ninenode.borrow().parent.as_ref().unwrap().upgrade().unwrap().borrow().key.unwrap());
     println!("PRE HEAD: {:?}", rootlink.borrow().key.unwrap());
     let senode = rootlink.clone().borrow_mut().tree_search(&17).unwrap();
     BstNode::tree_delete(&mut rootlink.clone(), &senode.clone());
+    viewer.record("delete 17", &rootlink);
     println!("POST HEAD: {:?}", rootlink.borrow().key.unwrap());
     let main_tree_path = "bst_graph2.dot";
-    generate_dotfile_bst(&rootlink, main_tree_path);
+    generate_dotfile_bst(&rootlink, main_tree_path).expect("Failed to write the dot file");
+    viewer.write("bst_graph_viewer.html").expect("Failed to write the viewer");
     let senode = rootlink.clone().borrow_mut().right.as_ref().unwrap().borrow_mut().key.unwrap();
     println!("head of right subtree: {:?}", senode);
 }
@@ -187,6 +228,33 @@ fn main() {
 //     }
 // }
 
+fn run_trace() {
+    let args: Vec<String> = std::env::args().collect();
+    let (Some(operation), Some(key)) = (args.get(2), args.get(3).and_then(|key| key.parse::<i32>().ok())) else {
+        println!("usage: trace <insert|search|successor|delete> <key> [dir]");
+        return;
+    };
+    let dir = std::path::PathBuf::from(args.get(4).map_or("trace", |dir| dir.as_str()));
+    std::fs::create_dir_all(&dir).expect("Failed to create");
+    let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
+    for key in [6, 18, 17, 20, 3, 7, 2, 4, 13, 9] {
+        BstNode::tree_insert(&rootlink, key);
+    }
+    let steps = match operation.as_str() {
+        "insert" => trace::trace_insert(&rootlink, key, &dir),
+        "search" => trace::trace_search(&rootlink, key, &dir),
+        "successor" => trace::trace_successor(&rootlink, key, &dir),
+        "delete" => trace::trace_delete(&rootlink, key, &dir),
+        _ => {
+            println!("unknown operation {}", operation);
+            return;
+        }
+    };
+    for step in steps.expect("Failed to write the trace") {
+        println!("{}: {}", step.file.display(), step.narration);
+    }
+}
+
 #[allow(dead_code)]
 fn test_binary_tree() {
     //create the nodelink of the root node
@@ -201,7 +269,7 @@ fn test_binary_tree() {
 
     //print the tree at this time
     let mut main_tree_path = "prime.dot";
-    generate_dotfile(&rootlink, main_tree_path);
+    generate_dotfile(&rootlink, main_tree_path).expect("Failed to write the dot file");
 
     //add new child values to the left subtree
     let left_subtree = &rootlink.borrow().left;
@@ -224,7 +292,7 @@ fn test_binary_tree() {
 
     //print the tree again, now been added with more values
     main_tree_path = "prime_t2.dot";
-    generate_dotfile(&rootlink, main_tree_path);
+    generate_dotfile(&rootlink, main_tree_path).expect("Failed to write the dot file");
 
     //Call tree depth function at this time
     let recorded_depth = rootlink.borrow().tree_depth();
@@ -240,7 +308,7 @@ fn test_binary_tree() {
     println!("Amount of nodes in current subtree: {0}", subtree_count);
 
     //Get the sibling of the leftsubtree from parent
-    let _left_subtree_sibling = Node::get_sibling(&left_subtree.as_ref().unwrap());
+    let _left_subtree_sibling = Node::get_sibling(left_subtree.as_ref().unwrap());
     //println!("sibling of left subtree {:?}", left_subtree_sibling);
 
     //get the left subtree by value
@@ -249,7 +317,7 @@ fn test_binary_tree() {
     //get the left subtree by full properties
     let another_left_subtree = rootlink
         .borrow()
-        .get_node_by_full_property(&left_subtree.as_ref().unwrap());
+        .get_node_by_full_property(left_subtree.as_ref().unwrap());
     println!(
         "left subtree seek by full property {:?}",
         another_left_subtree
@@ -263,7 +331,7 @@ fn test_binary_tree() {
 
     //print the tree again
     main_tree_path = "prime_t3.dot";
-    generate_dotfile(&rootlink2, main_tree_path);
+    generate_dotfile(&rootlink2, main_tree_path).expect("Failed to write the dot file");
 
     //Call tree depth function at this time
     //TODO
@@ -276,5 +344,5 @@ fn test_binary_tree() {
 
     //print the tree again
     main_tree_path = "prime_t4.dot";
-    generate_dotfile(&rootlink, main_tree_path);
+    generate_dotfile(&rootlink, main_tree_path).expect("Failed to write the dot file");
 }
\ No newline at end of file
@@ -0,0 +1,212 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::{Node, NodeLink};
+use std::rc::Rc;
+
+/**
+ * Ancestor queries built on top of the Weak parent pointers
+ * tree::Node values may repeat, so nodes are told apart by identity only: the links passed in have to be the ones
+ * stored in the tree (taken from left / right), not copies from get_node_by_value or get_nodelink_copy
+ * BstNode keys are unique, so a copy handed out by tree_search is swapped for the link stored below its parent
+ */
+impl Node {
+    /**
+     * Every node from node itself up to the root, root is the last element
+     */
+    pub fn path_to_root(node: &NodeLink) -> Vec<NodeLink> {
+        let mut path = vec![node.clone()];
+        let mut current = Node::upgrade_weak_to_strong(node.borrow().parent.clone());
+        while let Some(ancestor) = current {
+            current = Node::upgrade_weak_to_strong(ancestor.borrow().parent.clone());
+            path.push(ancestor);
+        }
+        path
+    }
+
+    /**
+     * Number of edges between node and the root, the root itself is at depth 0
+     */
+    pub fn depth_of(node: &NodeLink) -> usize {
+        Node::path_to_root(node).len() - 1
+    }
+
+    /**
+     * Deepest node that has both a and b below it (a node counts as its own ancestor)
+     * Return None if a and b live in different trees
+     */
+    pub fn lowest_common_ancestor(a: &NodeLink, b: &NodeLink) -> Option<NodeLink> {
+        let a_path = Node::path_to_root(a);
+        let b_path = Node::path_to_root(b);
+        //walk both paths down from the root, the last shared node is the answer
+        let mut common = None;
+        for (a_node, b_node) in a_path.iter().rev().zip(b_path.iter().rev()) {
+            if !Rc::ptr_eq(a_node, b_node) {
+                break;
+            }
+            common = Some(a_node.clone());
+        }
+        common
+    }
+
+    /**
+     * Number of edges on the path between a and b, None if they live in different trees
+     */
+    pub fn distance(a: &NodeLink, b: &NodeLink) -> Option<usize> {
+        let ancestor = Node::lowest_common_ancestor(a, b)?;
+        let ancestor_depth = Node::depth_of(&ancestor);
+        Some(Node::depth_of(a) + Node::depth_of(b) - 2 * ancestor_depth)
+    }
+}
+
+fn same_link<T>(a: &Option<Rc<T>>, b: &Option<Rc<T>>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+impl BstNode {
+    /**
+     * Every node from node itself up to the root, root is the last element
+     * The nodes returned are the ones stored in the tree, not copies
+     */
+    pub fn path_to_root(node: &BstNodeLink) -> Vec<BstNodeLink> {
+        let mut path = Vec::new();
+        let mut current = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
+        while let Some(ancestor) = current {
+            current = BstNode::upgrade_weak_to_strong(ancestor.borrow().parent.clone());
+            path.push(ancestor);
+        }
+        //keys are unique, so the starting node is the child of its parent with the same key
+        let stored = match path.first() {
+            Some(parent) => {
+                let key = node.borrow().key;
+                let parent = parent.borrow();
+                let stored = [&parent.left, &parent.right]
+                    .into_iter()
+                    .flatten()
+                    .find(|child| child.borrow().key == key)
+                    .cloned();
+                stored.unwrap_or_else(|| node.clone())
+            }
+            None => node.clone(),
+        };
+        path.insert(0, stored);
+        path
+    }
+
+    /**
+     * Number of edges between node and the root, the root itself is at depth 0
+     */
+    pub fn depth_of(node: &BstNodeLink) -> usize {
+        let mut depth = 0;
+        let mut current = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
+        while let Some(ancestor) = current {
+            depth += 1;
+            current = BstNode::upgrade_weak_to_strong(ancestor.borrow().parent.clone());
+        }
+        depth
+    }
+
+    /**
+     * Deepest node that has both a and b below it (a node counts as its own ancestor)
+     * Only the keys and the shared root are needed: descend from the root until a and b split to different sides
+     * Return None if a and b live in different trees
+     */
+    pub fn lowest_common_ancestor(a: &BstNodeLink, b: &BstNodeLink) -> Option<BstNodeLink> {
+        let root = BstNode::get_root(a);
+        let other_root = BstNode::get_root(b);
+        //either root may be a copy handed out by tree_search, copies still share their children
+        let same_root = Rc::ptr_eq(&root, &other_root) || {
+            let (root, other_root) = (root.borrow(), other_root.borrow());
+            root.key == other_root.key
+                && same_link(&root.left, &other_root.left)
+                && same_link(&root.right, &other_root.right)
+        };
+        if !same_root {
+            return None;
+        }
+        let a_key = a.borrow().key?;
+        let b_key = b.borrow().key?;
+        let mut current = root;
+        loop {
+            let key = current.borrow().key?;
+            let next = if a_key < key && b_key < key {
+                current.borrow().left.clone()
+            } else if a_key > key && b_key > key {
+                current.borrow().right.clone()
+            } else {
+                return Some(current);
+            };
+            current = next?;
+        }
+    }
+
+    /**
+     * Number of edges on the path between a and b, None if they live in different trees
+     */
+    pub fn distance(a: &BstNodeLink, b: &BstNodeLink) -> Option<usize> {
+        let ancestor = BstNode::lowest_common_ancestor(a, b)?;
+        let ancestor_depth = BstNode::depth_of(&ancestor);
+        Some(BstNode::depth_of(a) + BstNode::depth_of(b) - 2 * ancestor_depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structure::bst::{BstNode, BstNodeLink};
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::demo_bst;
+    use crate::tool::tree_text;
+
+    #[test]
+    fn test_ancestry() {
+        let rootlink = demo_bst();
+        let search = |key: i32| rootlink.borrow().tree_search(&key).unwrap();
+        let keys = |path: Vec<BstNodeLink>| path.iter().map(|x| x.borrow().key.unwrap()).collect::<Vec<i32>>();
+
+        assert_eq!(keys(BstNode::path_to_root(&search(9))), vec![9, 13, 7, 6, 15]);
+        assert_eq!(BstNode::depth_of(&search(9)), 4);
+        assert_eq!(BstNode::depth_of(&rootlink), 0);
+        assert_eq!(BstNode::lowest_common_ancestor(&search(2), &search(9)).unwrap().borrow().key, Some(6));
+        assert_eq!(BstNode::lowest_common_ancestor(&search(9), &search(17)).unwrap().borrow().key, Some(15));
+        assert_eq!(BstNode::lowest_common_ancestor(&search(15), &search(4)).unwrap().borrow().key, Some(15));
+        assert_eq!(BstNode::distance(&search(15), &search(4)), Some(3));
+        //a node is its own ancestor
+        assert_eq!(BstNode::lowest_common_ancestor(&search(7), &search(9)).unwrap().borrow().key, Some(7));
+        //the result is the node stored in the tree, not a copy
+        let ancestor = BstNode::lowest_common_ancestor(&search(2), &search(4)).unwrap();
+        assert!(std::rc::Rc::ptr_eq(&ancestor, rootlink.borrow().left.as_ref().unwrap().borrow().left.as_ref().unwrap()));
+        assert_eq!(BstNode::distance(&search(2), &search(9)), Some(5));
+        assert_eq!(BstNode::distance(&search(9), &search(9)), Some(0));
+        assert_eq!(BstNode::distance(&search(20), &search(17)), Some(2));
+        let other_tree = BstNode::new_bst_nodelink(9);
+        assert!(BstNode::lowest_common_ancestor(&search(9), &other_tree).is_none());
+        assert_eq!(BstNode::distance(&other_tree, &search(9)), None);
+
+        //tree::Node follows the parent pointers only, values don't have to be ordered
+        let treelink = tree_text::read_treefile(concat!(env!("CARGO_MANIFEST_DIR"), "/prime_t4")).unwrap();
+        let forty_eight = treelink.borrow().left.clone().unwrap();
+        let eight_eight_eight = treelink.borrow().right.clone().unwrap();
+        let eighty_four = eight_eight_eight.borrow().left.clone().unwrap();
+        let values = |path: Vec<NodeLink>| path.iter().map(|x| x.borrow().value).collect::<Vec<i32>>();
+        assert_eq!(values(Node::path_to_root(&eighty_four)), vec![84, 888, 88]);
+        assert_eq!(Node::depth_of(&eighty_four), 2);
+        assert_eq!(Node::lowest_common_ancestor(&forty_eight, &eighty_four).unwrap().borrow().value, 88);
+        assert_eq!(Node::distance(&forty_eight, &eighty_four), Some(3));
+        assert!(std::rc::Rc::ptr_eq(
+            &Node::lowest_common_ancestor(&eight_eight_eight, &eighty_four).unwrap(),
+            &eight_eight_eight
+        ));
+        //sibling leaves with the same value are still two different nodes
+        let twins = Node::new_nodelink(5);
+        twins.borrow_mut().add_left_child(&twins, 7);
+        twins.borrow_mut().add_right_child(&twins, 7);
+        let left = twins.borrow().left.clone().unwrap();
+        let right = twins.borrow().right.clone().unwrap();
+        assert!(std::rc::Rc::ptr_eq(&Node::lowest_common_ancestor(&left, &right).unwrap(), &twins));
+        assert_eq!(Node::distance(&left, &right), Some(2));
+        assert_eq!(Node::distance(&left, &left), Some(0));
+        assert!(Node::lowest_common_ancestor(&eighty_four, &Node::new_nodelink(84)).is_none());
+    }
+}
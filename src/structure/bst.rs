@@ -1,4 +1,6 @@
+use crate::structure::cost::{CostCounter, NoCost};
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::rc::{Rc, Weak};
 
 pub type BstNodeLink = Rc<RefCell<BstNode>>;
@@ -26,14 +28,18 @@ impl BstNode {
 
     pub fn new_bst_nodelink(value: i32) -> BstNodeLink {
         let currentnode = BstNode::new(value);
-        let currentlink = Rc::new(RefCell::new(currentnode));
-        currentlink
+        Rc::new(RefCell::new(currentnode))
     }
 
     /**
      * Get a copy of node link
      */
     pub fn get_bst_nodelink_copy(&self) -> BstNodeLink {
+        self.copy_counted(&mut NoCost)
+    }
+
+    fn copy_counted(&self, costs: &mut impl CostCounter) -> BstNodeLink {
+        costs.allocation();
         Rc::new(RefCell::new(self.clone()))
     }
 
@@ -42,72 +48,120 @@ impl BstNode {
     }
 
     //private interface
-    fn new_with_parent(parent: &BstNodeLink, value: i32) -> BstNodeLink {
+    fn new_with_parent(parent: &BstNodeLink, value: i32, costs: &mut impl CostCounter) -> BstNodeLink {
         let mut currentnode = BstNode::new(value);
         //currentnode.add_parent(Rc::<RefCell<BstNode>>::downgrade(parent));
         currentnode.parent = Some(BstNode::downgrade(parent));
-        let currentlink = Rc::new(RefCell::new(currentnode));
-        currentlink
+        costs.allocation();
+        Rc::new(RefCell::new(currentnode))
     }
 
     //add new left child, set the parent to current_node_link
     pub fn add_left_child(&mut self, current_node_link: &BstNodeLink, value: i32) {
-        let new_node = BstNode::new_with_parent(current_node_link, value);
+        let new_node = BstNode::new_with_parent(current_node_link, value, &mut NoCost);
         self.left = Some(new_node);
     }
 
     //add new left child, set the parent to current_node_link
     pub fn add_right_child(&mut self, current_node_link: &BstNodeLink, value: i32) {
-        let new_node = BstNode::new_with_parent(current_node_link, value);
+        let new_node = BstNode::new_with_parent(current_node_link, value, &mut NoCost);
         self.right = Some(new_node);
     }
 
     //search the current tree which node fit the value
+    //walks down in a loop, a degenerate (chain) tree would overflow the stack with recursion
     pub fn tree_search(&self, value: &i32) -> Option<BstNodeLink> {
-        if let Some(key) = self.key {
+        self.tree_search_counted(value, &mut NoCost)
+    }
+
+    //tree_search reporting what it does to costs, the same goes for every other *_counted variant
+    pub fn tree_search_counted(&self, value: &i32, costs: &mut impl CostCounter) -> Option<BstNodeLink> {
+        let key = self.key?;
+        costs.comparison();
+        if key == *value {
+            return Some(self.copy_counted(costs));
+        }
+        costs.comparison();
+        let mut next = if *value < key { self.left.clone() } else { self.right.clone() };
+        while let Some(current) = next {
+            costs.child_hop();
+            let current = current.borrow();
+            //default if current node is NIL
+            let key = current.key?;
+            costs.comparison();
             if key == *value {
-                return Some(self.get_bst_nodelink_copy());
-            }
-            if *value < key && self.left.is_some() {
-                return self.left.as_ref().unwrap().borrow().tree_search(value);
-            } else if self.right.is_some() {
-                return self.right.as_ref().unwrap().borrow().tree_search(value);
+                return Some(current.copy_counted(costs));
             }
+            costs.comparison();
+            next = if *value < key { current.left.clone() } else { current.right.clone() };
         }
-        //default if current node is NIL
         None
     }
 
-    /**seek minimum by recurs
+    /**seek minimum by following the left links
      * in BST minimum always on the left
      */
     pub fn minimum(&self) -> BstNodeLink {
-        if self.key.is_some() {
-            if let Some(left_node) = &self.left {
-                return left_node.borrow().minimum();
-            }
+        self.minimum_counted(&mut NoCost)
+    }
+
+    pub fn minimum_counted(&self, costs: &mut impl CostCounter) -> BstNodeLink {
+        let mut next = match self.key {
+            Some(_) => self.left.clone(),
+            None => None,
+        };
+        let mut lowest = None;
+        while let Some(current) = next {
+            costs.child_hop();
+            next = current.borrow().left.clone();
+            lowest = Some(current);
+        }
+        match lowest {
+            Some(lowest) => lowest.borrow().copy_counted(costs),
+            None => self.copy_counted(costs),
         }
-        self.get_bst_nodelink_copy()
     }
 
     pub fn maximum(&self) -> BstNodeLink {
-        if self.key.is_some() {
-            if let Some(right_node) = &self.right {
-                return right_node.borrow().maximum();
-            }
+        self.maximum_counted(&mut NoCost)
+    }
+
+    pub fn maximum_counted(&self, costs: &mut impl CostCounter) -> BstNodeLink {
+        let mut next = match self.key {
+            Some(_) => self.right.clone(),
+            None => None,
+        };
+        let mut highest = None;
+        while let Some(current) = next {
+            costs.child_hop();
+            next = current.borrow().right.clone();
+            highest = Some(current);
+        }
+        match highest {
+            Some(highest) => highest.borrow().copy_counted(costs),
+            None => self.copy_counted(costs),
         }
-        self.get_bst_nodelink_copy()
     }
 
     /**
      * Return the root of a node, return self if not exist
      */
     pub fn get_root(node: &BstNodeLink) -> BstNodeLink {
-        let parent = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
-        if parent.is_none() {
-            return node.clone();
+        BstNode::get_root_counted(node, &mut NoCost)
+    }
+
+    pub fn get_root_counted(node: &BstNodeLink, costs: &mut impl CostCounter) -> BstNodeLink {
+        let mut current = node.clone();
+        loop {
+            let parent = BstNode::upgrade_counted(current.borrow().parent.clone(), costs);
+            match parent {
+                Some(parent) => {
+                    costs.parent_hop();
+                    current = parent;
+                }
+                None => return current,
+            }
         }
-        return BstNode::get_root(&parent.unwrap());
     }
 
     /**
@@ -116,122 +170,337 @@ impl BstNode {
      * Should return None, if x_node is the highest key in the tree
      */
     pub fn tree_successor(x_node: &BstNodeLink) -> Option<BstNodeLink> {
+        BstNode::tree_successor_counted(x_node, &mut NoCost)
+    }
+
+    pub fn tree_successor_counted(x_node: &BstNodeLink, costs: &mut impl CostCounter) -> Option<BstNodeLink> {
         // directly check if the node has a right child, otherwise go to the next block
         if let Some(right_node) = &x_node.borrow().right {
-            return Some(right_node.borrow().minimum());
+            return Some(right_node.borrow().minimum_counted(costs));
         }
         // empty right child case
         else {
             let mut x_node = x_node;
-            let mut y_node = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone());
+            let mut y_node = BstNode::upgrade_counted(x_node.borrow().parent.clone(), costs);
             let mut temp: BstNodeLink;
 
             while let Some(ref exist) = y_node {
                 if let Some(ref left_child) = exist.borrow().left {
-                    if BstNode::is_node_match(left_child, x_node) {
+                    if BstNode::is_node_match(left_child, x_node, costs) {
                         return Some(exist.clone());
                     }
                 }
 
                 temp = y_node.unwrap();
+                costs.parent_hop();
                 x_node = &temp;
-                y_node = BstNode::upgrade_weak_to_strong(temp.borrow().parent.clone());
+                y_node = BstNode::upgrade_counted(temp.borrow().parent.clone(), costs);
             }
 
             None
         }
     }
 
-    pub fn tree_insert(currlink: &BstNodeLink, value: i32) {
-        if currlink.borrow().key.is_none() {
-            currlink.borrow_mut().key = Some(value);
-        } else if value < currlink.borrow().key.unwrap() {
-            if currlink.borrow().left.is_none() {
-                currlink.borrow_mut().add_left_child(&currlink, value);
-            } else {
-                BstNode::tree_insert(&currlink.borrow_mut().clone().left.as_ref().unwrap(), value);
+    /**
+     * Mirror of tree_successor, return the node with the next lower key
+     * Should return None, if x_node is the lowest key in the tree
+     */
+    pub fn tree_predecessor(x_node: &BstNodeLink) -> Option<BstNodeLink> {
+        BstNode::tree_predecessor_counted(x_node, &mut NoCost)
+    }
+
+    pub fn tree_predecessor_counted(x_node: &BstNodeLink, costs: &mut impl CostCounter) -> Option<BstNodeLink> {
+        if let Some(left_node) = &x_node.borrow().left {
+            return Some(left_node.borrow().maximum_counted(costs));
+        }
+        //no left child, go up until we come from a right child
+        let mut x_node = x_node.clone();
+        let mut y_node = BstNode::upgrade_counted(x_node.borrow().parent.clone(), costs);
+        while let Some(exist) = y_node {
+            if exist
+                .borrow()
+                .right
+                .as_ref()
+                .is_some_and(|right_child| BstNode::is_node_match(right_child, &x_node, costs))
+            {
+                return Some(exist);
             }
-        } else if value > currlink.borrow().key.unwrap() {
-            if currlink.borrow().right.is_none() {
-                currlink.borrow_mut().add_right_child(&currlink, value);
-            } else {
-                BstNode::tree_insert(
-                    &currlink.borrow_mut().clone().right.as_ref().unwrap(),
-                    value,
-                );
+            costs.parent_hop();
+            y_node = BstNode::upgrade_counted(exist.borrow().parent.clone(), costs);
+            x_node = exist;
+        }
+        None
+    }
+
+    //descend in a loop until the free slot, duplicates are ignored
+    pub fn tree_insert(currlink: &BstNodeLink, value: i32) {
+        BstNode::tree_insert_counted(currlink, value, &mut NoCost)
+    }
+
+    pub fn tree_insert_counted(currlink: &BstNodeLink, value: i32, costs: &mut impl CostCounter) {
+        let mut currlink = currlink.clone();
+        loop {
+            let key = match currlink.borrow().key {
+                Some(key) => key,
+                None => break,
+            };
+            costs.comparison();
+            let next = match value.cmp(&key) {
+                Ordering::Less => currlink.borrow().left.clone(),
+                Ordering::Greater => currlink.borrow().right.clone(),
+                Ordering::Equal => return,
+            };
+            match next {
+                Some(next) => {
+                    costs.child_hop();
+                    currlink = next;
+                }
+                None if value < key => {
+                    currlink.borrow_mut().left = Some(BstNode::new_with_parent(&currlink, value, costs));
+                    return;
+                }
+                None => {
+                    currlink.borrow_mut().right = Some(BstNode::new_with_parent(&currlink, value, costs));
+                    return;
+                }
             }
         }
+        currlink.borrow_mut().key = Some(value);
     }
 
+    /**
+     * Replace the subtree rooted at u with the subtree rooted at v
+     * The root link is shared by the callers (main keeps its own clone), so when u is the root
+     * the content of v is moved into the root node instead of swapping the root link,
+     * an empty v leaves the root with key None
+     */
     pub fn transplant(root: &mut BstNodeLink, u: &BstNodeLink, v: &Option<BstNodeLink>) {
-        let upar = u.borrow().parent.clone();
-        if upar.is_none() {
-            root.clone().borrow_mut().key = v.clone().unwrap().borrow().key.clone();
-            if !(BstNode::tree_successor(&root).unwrap().borrow().key.unwrap() == root.clone().borrow().right.as_ref().unwrap().borrow().key.unwrap()) {
-                root.clone().borrow_mut().right.as_ref().unwrap().borrow_mut().parent = Some(Rc::downgrade(&root.clone()));
-            } else {
-                root.clone().borrow_mut().right = v.clone().unwrap().borrow().right.clone();
+        BstNode::transplant_counted(root, u, v, &mut NoCost)
+    }
+
+    pub fn transplant_counted(root: &mut BstNodeLink, u: &BstNodeLink, v: &Option<BstNodeLink>, costs: &mut impl CostCounter) {
+        let upar = BstNode::upgrade_counted(u.borrow().parent.clone(), costs);
+        match upar {
+            None => {
+                let (key, left, right) = match v {
+                    Some(v_node) => {
+                        let v_node = v_node.borrow();
+                        (v_node.key, v_node.left.clone(), v_node.right.clone())
+                    }
+                    None => (None, None, None),
+                };
+                let mut rootmut = root.borrow_mut();
+                rootmut.key = key;
+                rootmut.left = left;
+                rootmut.right = right;
+                for child in [&rootmut.left, &rootmut.right].into_iter().flatten() {
+                    child.borrow_mut().parent = Some(BstNode::downgrade(root));
+                }
             }
-        } else {
-            let upars = BstNode::upgrade_weak_to_strong(upar.clone()).unwrap();
-            let mut parmut = upars.borrow_mut();
-            if let Some(left) = parmut.left.clone() {
-                if BstNode::is_node_match(&left, &u.clone()) {
+            Some(upars) => {
+                let mut parmut = upars.borrow_mut();
+                if parmut.left.as_ref().is_some_and(|left| BstNode::is_node_match(left, u, costs)) {
                     parmut.left = v.clone();
                 } else {
                     parmut.right = v.clone();
                 }
-            } else {
-                parmut.right = v.clone();
-            } if let Some(v_node) = v.clone() {
-                if let Some(upar2) = u.borrow().parent.clone() {
-                    v_node.clone().borrow_mut().parent = Some(upar2.clone());
+                if let Some(v_node) = v {
+                    v_node.borrow_mut().parent = Some(BstNode::downgrade(&upars));
                 }
             }
         }
     }
 
     pub fn tree_delete(root: &mut BstNodeLink, z: &BstNodeLink) {
-        let z_left = z.borrow_mut().left.clone();
-        let z_right = z.borrow_mut().right.clone();
-
-        if z_left.clone().is_none() {
-            BstNode::transplant(root, &z, &z_right.clone());
-        } else if z_right.clone().is_none() {
-            BstNode::transplant(root, &z, &z_left.clone());
-        } else {
-            let z_right_node = z_right.clone().unwrap();
-            let successor = BstNode::tree_successor(&z);
-
-            if let Some(y) = &successor.clone() {
-                if !(BstNode::is_node_match(&y, &z_right_node)) {
+        BstNode::tree_delete_counted(root, z, &mut NoCost)
+    }
+
+    pub fn tree_delete_counted(root: &mut BstNodeLink, z: &BstNodeLink, costs: &mut impl CostCounter) {
+        let z_left = z.borrow().left.clone();
+        let z_right = z.borrow().right.clone();
+
+        match (z_left, z_right) {
+            (None, z_right) => BstNode::transplant_counted(root, z, &z_right, costs),
+            (z_left, None) => BstNode::transplant_counted(root, z, &z_left, costs),
+            (Some(z_left_node), Some(z_right_node)) => {
+                //the successor is the minimum of the right subtree, take the link stored in the tree
+                let successor = BstNode::tree_successor_counted(z, costs).unwrap();
+                let y = BstNode::resolve_link_counted(root, &successor, costs);
+
+                if !BstNode::is_node_match(&y, &z_right_node, costs) {
                     let successor_right = y.borrow().right.clone();
-                    BstNode::transplant(root, &y.clone(), &successor_right);
-                    y.as_ref().borrow_mut().right = Some(z_right_node.clone());
-                    y
-                        .borrow_mut()
-                        .right
-                        .as_ref()
-                        .unwrap()
-                        .borrow_mut()
-                        .parent = Some(BstNode::downgrade(&y));
+                    BstNode::transplant_counted(root, &y, &successor_right, costs);
+                    y.borrow_mut().right = Some(z_right_node.clone());
+                    z_right_node.borrow_mut().parent = Some(BstNode::downgrade(&y));
                 }
-                let z_left_for_assign = BstNode::new_bst_nodelink(z.borrow().left.as_ref().unwrap().borrow().key.unwrap());
-                z_left_for_assign.borrow_mut().parent = Some(BstNode::downgrade(&y));
-                z_left_for_assign.borrow_mut().left = z.borrow().left.clone().unwrap().borrow().left.clone();
-                z_left_for_assign.borrow_mut().right = z.borrow().left.clone().unwrap().borrow().right.clone();
-                y.clone().borrow_mut().left = Some(z_left_for_assign);
-                BstNode::transplant(root, z, &Some(y.clone()));
-                y
-                    .clone()
-                    .borrow_mut()
+                //hang z's left subtree below y before y takes z's place, moving y into the root copies its children
+                y.borrow_mut().left = Some(z_left_node.clone());
+                z_left_node.borrow_mut().parent = Some(BstNode::downgrade(&y));
+                BstNode::transplant_counted(root, z, &Some(y), costs);
+            }
+        }
+    }
+
+    /**
+     * Rotate left around x, x.right (y) takes the place of x and x becomes y.left
+     * y.left is handed over to x.right, parent pointers of every moved node are updated
+     * If x was the top of the tree, root is moved to y
+     * Nothing happens when x has no right child
+     */
+    pub fn rotate_left(root: &mut BstNodeLink, x: &BstNodeLink) {
+        BstNode::rotate_left_counted(root, x, &mut NoCost)
+    }
+
+    pub fn rotate_left_counted(root: &mut BstNodeLink, x: &BstNodeLink, costs: &mut impl CostCounter) {
+        let x = BstNode::resolve_link_counted(root, x, costs);
+        let y = match x.borrow().right.clone() {
+            Some(y) => y,
+            None => return,
+        };
+        costs.rotation();
+        //turn y's left subtree into x's right subtree
+        let y_left = y.borrow_mut().left.take();
+        if let Some(beta) = &y_left {
+            beta.borrow_mut().parent = Some(BstNode::downgrade(&x));
+        }
+        x.borrow_mut().right = y_left;
+        //link x's parent to y
+        BstNode::replace_child(root, &x, &y, costs);
+        //put x on y's left
+        y.borrow_mut().left = Some(x.clone());
+        x.borrow_mut().parent = Some(BstNode::downgrade(&y));
+    }
+
+    /**
+     * Mirror of rotate_left, y.left (x) takes the place of y and y becomes x.right
+     * If y was the top of the tree, root is moved to x
+     * Nothing happens when y has no left child
+     */
+    pub fn rotate_right(root: &mut BstNodeLink, y: &BstNodeLink) {
+        BstNode::rotate_right_counted(root, y, &mut NoCost)
+    }
+
+    pub fn rotate_right_counted(root: &mut BstNodeLink, y: &BstNodeLink, costs: &mut impl CostCounter) {
+        let y = BstNode::resolve_link_counted(root, y, costs);
+        let x = match y.borrow().left.clone() {
+            Some(x) => x,
+            None => return,
+        };
+        costs.rotation();
+        //turn x's right subtree into y's left subtree
+        let x_right = x.borrow_mut().right.take();
+        if let Some(beta) = &x_right {
+            beta.borrow_mut().parent = Some(BstNode::downgrade(&y));
+        }
+        y.borrow_mut().left = x_right;
+        //link y's parent to x
+        BstNode::replace_child(root, &y, &x, costs);
+        //put y on x's right
+        x.borrow_mut().right = Some(y.clone());
+        y.borrow_mut().parent = Some(BstNode::downgrade(&x));
+    }
+
+    //hang new_child where old_child used to be, either below old_child's parent or as the new root
+    fn replace_child(root: &mut BstNodeLink, old_child: &BstNodeLink, new_child: &BstNodeLink, costs: &mut impl CostCounter) {
+        let old_parent = BstNode::upgrade_counted(old_child.borrow().parent.clone(), costs);
+        new_child.borrow_mut().parent = old_child.borrow().parent.clone();
+        match old_parent {
+            None if Rc::ptr_eq(root, old_child) => *root = new_child.clone(),
+            //top of a detached subtree, there is nothing above it to relink
+            None => {}
+            Some(parent) => {
+                let mut parent_mut = parent.borrow_mut();
+                if parent_mut
                     .left
                     .as_ref()
-                    .unwrap()
-                    .borrow_mut()
-                    .parent = Some(BstNode::downgrade(&y.clone()));
+                    .is_some_and(|left| BstNode::is_node_match(left, old_child, costs))
+                {
+                    parent_mut.left = Some(new_child.clone());
+                } else {
+                    parent_mut.right = Some(new_child.clone());
+                }
+            }
+        }
+    }
+
+    /**
+     * tree_search and minimum hand out copies of a node, rotating a copy would leave the tree untouched
+     * so find the link that is actually stored in the tree for the same key
+     * A node without parent only stands for the root when it is the root or a copy of it (same key, same children),
+     * any other detached node is handed back as it is
+     */
+    pub fn resolve_link(root: &BstNodeLink, node: &BstNodeLink) -> BstNodeLink {
+        BstNode::resolve_link_counted(root, node, &mut NoCost)
+    }
+
+    fn resolve_link_counted(root: &BstNodeLink, node: &BstNodeLink, costs: &mut impl CostCounter) -> BstNodeLink {
+        match BstNode::upgrade_counted(node.borrow().parent.clone(), costs) {
+            None => {
+                let is_root = Rc::ptr_eq(root, node) || {
+                    let (root_ref, node_ref) = (root.borrow(), node.borrow());
+                    let same_child = |a: &Option<BstNodeLink>, b: &Option<BstNodeLink>| match (a, b) {
+                        (None, None) => true,
+                        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+                        _ => false,
+                    };
+                    root_ref.key == node_ref.key
+                        && same_child(&root_ref.left, &node_ref.left)
+                        && same_child(&root_ref.right, &node_ref.right)
+                };
+                if is_root {
+                    root.clone()
+                } else {
+                    node.clone()
+                }
+            }
+            Some(parent) => {
+                let parent = parent.borrow();
+                for child in [&parent.left, &parent.right].into_iter().flatten() {
+                    if BstNode::is_node_match(child, node, costs) {
+                        return child.clone();
+                    }
+                }
+                node.clone()
+            }
+        }
+    }
+
+    /**
+     * Number of edges on the longest path down from node, 0 for a single node or an empty tree
+     */
+    pub fn height(node: &BstNodeLink) -> usize {
+        let mut height = 0;
+        let mut stack: Vec<(BstNodeLink, usize)> = vec![(node.clone(), 0)];
+        while let Some((current, depth)) = stack.pop() {
+            height = height.max(depth);
+            let current = current.borrow();
+            for child in [&current.left, &current.right].into_iter().flatten() {
+                stack.push((child.clone(), depth + 1));
             }
         }
+        height
+    }
+
+    /**
+     * Collect the keys in sorted (in-order) sequence starting from node
+     */
+    pub fn inorder_keys(node: &BstNodeLink) -> Vec<i32> {
+        let mut keys = Vec::new();
+        let mut stack: Vec<BstNodeLink> = Vec::new();
+        let mut next = Some(node.clone());
+        loop {
+            //slide down the left spine, then take the lowest pending node and go right
+            while let Some(current) = next {
+                next = current.borrow().left.clone();
+                stack.push(current);
+            }
+            let Some(current) = stack.pop() else {
+                break;
+            };
+            keys.extend(current.borrow().key);
+            next = current.borrow().right.clone();
+        }
+        keys
     }
 
     /**
@@ -242,7 +511,7 @@ impl BstNode {
         //create a shadow of x_node so it can mutate
         let mut x_node = x_node;
         let right_node = &x_node.borrow().right.clone();
-        if BstNode::is_nil(right_node) != true {
+        if !BstNode::is_nil(right_node) {
             return Some(right_node.clone().unwrap().borrow().minimum());
         }
 
@@ -259,12 +528,12 @@ impl BstNode {
         }
 
         //in case our sucessor traversal yield root, means self is the highest key
-        if BstNode::is_node_match_option(y_node.clone(), Some(BstNode::get_root(&x_node))) {
+        if BstNode::is_node_match_option(y_node.clone(), Some(BstNode::get_root(x_node))) {
             return None;
         }
 
         //default return self / x_node
-        return Some(y_node.clone().unwrap());
+        Some(y_node.clone().unwrap())
     }
 
     /**
@@ -280,7 +549,7 @@ impl BstNode {
                 {
                     return true;
                 }
-                return false;
+                false
             }
         }
     }
@@ -293,23 +562,169 @@ impl BstNode {
         if let Some(node1v) = node1 {
             return node2.is_some_and(|x: BstNodeLink| x.borrow().key == node1v.borrow().key);
         }
-        return false;
+        false
     }
 
-    fn is_node_match(anode: &BstNodeLink, bnode: &BstNodeLink) -> bool {
+    fn is_node_match(anode: &BstNodeLink, bnode: &BstNodeLink, costs: &mut impl CostCounter) -> bool {
+        costs.comparison();
         if anode.borrow().key == bnode.borrow().key {
             return true;
         }
-        return false;
+        false
     }
 
     /**
      * As the name implied, used to upgrade parent node to strong nodelink
      */
     pub fn upgrade_weak_to_strong(node: Option<WeakBstNodeLink>) -> Option<BstNodeLink> {
-        match node {
-            None => None,
-            Some(x) => Some(x.upgrade().unwrap()),
+        BstNode::upgrade_counted(node, &mut NoCost)
+    }
+
+    fn upgrade_counted(node: Option<WeakBstNodeLink>, costs: &mut impl CostCounter) -> Option<BstNodeLink> {
+        node.map(|x| {
+            costs.weak_upgrade();
+            x.upgrade().unwrap()
+        })
+    }
+}
+
+/**
+ * The derived drop releases a node and then its children recursively, a long chain overflows the stack
+ * Detach the children instead and release them one by one from a heap stack
+ * A child still owned somewhere else (e.g. by a copy from tree_search) only loses this reference
+ */
+impl Drop for BstNode {
+    fn drop(&mut self) {
+        let mut stack: Vec<BstNodeLink> = Vec::new();
+        stack.extend(self.left.take());
+        stack.extend(self.right.take());
+        while let Some(node) = stack.pop() {
+            if let Ok(cell) = Rc::try_unwrap(node) {
+                let mut inner = cell.into_inner();
+                stack.extend(inner.left.take());
+                stack.extend(inner.right.take());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BstNode;
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::{chain, demo_bst, temp_path};
+    use crate::tool::{generate_dotfile, generate_dotfile_bst};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_rotation() {
+        let mut rootlink = demo_bst();
+        let expected = BstNode::inorder_keys(&rootlink);
+
+        //rotating at the top has to move the root reference
+        let top = rootlink.clone();
+        BstNode::rotate_left(&mut rootlink, &top);
+        assert_eq!(rootlink.borrow().key, Some(18));
+        assert!(rootlink.borrow().parent.is_none());
+        assert_eq!(BstNode::inorder_keys(&rootlink), expected);
+
+        //15 now sits on the left of 18 and got 17 as its right child
+        let fifteen = rootlink.borrow().left.clone().unwrap();
+        assert_eq!(fifteen.borrow().key, Some(15));
+        assert_eq!(fifteen.borrow().right.as_ref().unwrap().borrow().key, Some(17));
+        let seventeen_parent = fifteen.borrow().right.as_ref().unwrap().borrow().parent.clone();
+        assert_eq!(BstNode::upgrade_weak_to_strong(seventeen_parent).unwrap().borrow().key, Some(15));
+
+        //rotating back restores the original shape
+        let top = rootlink.clone();
+        BstNode::rotate_right(&mut rootlink, &top);
+        assert_eq!(rootlink.borrow().key, Some(15));
+        assert_eq!(BstNode::inorder_keys(&rootlink), expected);
+
+        //rotate an inner node found by tree_search, which hands out a copy
+        let seven = rootlink.borrow().tree_search(&7).unwrap();
+        BstNode::rotate_left(&mut rootlink, &seven);
+        let thirteen = rootlink.borrow().tree_search(&13).unwrap();
+        let thirteen_parent = BstNode::upgrade_weak_to_strong(thirteen.borrow().parent.clone()).unwrap();
+        assert_eq!(thirteen_parent.borrow().key, Some(6));
+        assert_eq!(thirteen.borrow().left.as_ref().unwrap().borrow().key, Some(7));
+        assert_eq!(BstNode::inorder_keys(&rootlink), expected);
+
+        let thirteen = rootlink.borrow().tree_search(&13).unwrap();
+        BstNode::rotate_right(&mut rootlink, &thirteen);
+        assert_eq!(BstNode::inorder_keys(&rootlink), expected);
+
+        //leaf rotation is a no-op
+        let two = rootlink.borrow().tree_search(&2).unwrap();
+        BstNode::rotate_left(&mut rootlink, &two);
+        assert_eq!(BstNode::inorder_keys(&rootlink), expected);
+
+        //a detached node is not the root, rotating it leaves the tree alone
+        let detached = BstNode::new_bst_nodelink(30);
+        BstNode::tree_insert(&detached, 40);
+        let forty = detached.borrow().right.clone().unwrap();
+        BstNode::rotate_left(&mut rootlink, &detached);
+        assert_eq!(rootlink.borrow().key, Some(15));
+        assert_eq!(BstNode::inorder_keys(&rootlink), expected);
+        //the detached pair itself got rotated
+        assert!(Rc::ptr_eq(forty.borrow().left.as_ref().unwrap(), &detached));
+        //a copy of the root still resolves to it
+        let root_copy = rootlink.borrow().get_bst_nodelink_copy();
+        assert!(Rc::ptr_eq(&BstNode::resolve_link(&rootlink, &root_copy), &rootlink));
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        const DEPTH: i32 = 1_000_000;
+        //sorted keys make a right spine, the inserts from the root walk all of it
+        let rootlink = chain(DEPTH);
+        BstNode::tree_insert(&rootlink, DEPTH);
+        BstNode::tree_insert(&rootlink, DEPTH / 2);
+        assert_eq!(rootlink.borrow().tree_search(&DEPTH).unwrap().borrow().key, Some(DEPTH));
+        assert!(rootlink.borrow().tree_search(&-1).is_none());
+        assert_eq!(rootlink.borrow().minimum().borrow().key, Some(0));
+        assert_eq!(rootlink.borrow().maximum().borrow().key, Some(DEPTH));
+        let deepest = rootlink.borrow().tree_search(&DEPTH).unwrap();
+        assert!(Rc::ptr_eq(&BstNode::get_root(&deepest), &rootlink));
+        drop(deepest);
+        let keys = BstNode::inorder_keys(&rootlink);
+        assert_eq!(keys.len(), DEPTH as usize + 1);
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(BstNode::preorder_keys(&rootlink), keys);
+        assert_eq!(BstNode::postorder_keys(&rootlink).len(), keys.len());
+        let rebuilt = BstNode::from_preorder(&keys).unwrap();
+        assert!(*rebuilt.borrow() == *rootlink.borrow());
+        assert_eq!(BstNode::structural_hash(&rebuilt), BstNode::structural_hash(&rootlink));
+        drop(rebuilt);
+        let dot_path = temp_path("deep_chain_test.dot");
+        generate_dotfile_bst(&rootlink, dot_path.to_str().unwrap()).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        let _ = std::fs::remove_file(&dot_path);
+        //a statement for every node, and every node but the last has a lone right child:
+        //the edge plus an invisible left placeholder and its edge
+        assert_eq!(text.lines().count(), 4 * DEPTH as usize + 4);
+        drop(rootlink);
+
+        //the same chain for the generic tree, hanging to the left this time
+        let treelink: NodeLink = Node::new_nodelink(0);
+        let mut tail = treelink.clone();
+        for value in 1..DEPTH {
+            tail.borrow_mut().add_left_child(&tail, value);
+            let next = tail.borrow().left.clone().unwrap();
+            tail = next;
         }
+        drop(tail);
+        assert_eq!(treelink.borrow().count_nodes(), DEPTH);
+        let values = Node::inorder_values(&treelink);
+        assert_eq!(values.first(), Some(&(DEPTH - 1)));
+        assert_eq!(Node::preorder_values(&treelink).len(), values.len());
+        let rebuilt = Node::from_postorder_inorder(&Node::postorder_values(&treelink), &values).unwrap();
+        assert!(*rebuilt.borrow() == *treelink.borrow());
+        drop(rebuilt);
+        assert_eq!(treelink.borrow().tree_depth(), DEPTH - 1);
+        let dot_path = temp_path("deep_chain_tree_test.dot");
+        generate_dotfile(&treelink, dot_path.to_str().unwrap()).unwrap();
+        let _ = std::fs::remove_file(&dot_path);
+        drop(treelink);
     }
 }
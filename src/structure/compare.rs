@@ -0,0 +1,260 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::{Node, NodeLink};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::cell::RefCell;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+//what the deep comparisons need from a node: the part that is compared and the two child slots
+trait Shape: Sized {
+    type Label: PartialEq + Hash;
+    fn label(&self) -> Self::Label;
+    fn children(&self) -> [&Child<Self>; 2];
+}
+
+type Child<T> = Option<Rc<RefCell<T>>>;
+
+impl Shape for Node {
+    type Label = i32;
+    fn label(&self) -> i32 {
+        self.value
+    }
+    fn children(&self) -> [&Option<NodeLink>; 2] {
+        [&self.left, &self.right]
+    }
+}
+
+impl Shape for BstNode {
+    type Label = Option<i32>;
+    fn label(&self) -> Option<i32> {
+        self.key
+    }
+    fn children(&self) -> [&Option<BstNodeLink>; 2] {
+        [&self.left, &self.right]
+    }
+}
+
+//pairs still to compare wait on a heap stack, so a chain of any depth is compared without recursion
+fn deep_eq<T: Shape>(a: &T, b: &T) -> bool {
+    if a.label() != b.label() {
+        return false;
+    }
+    let mut pending: Vec<(Child<T>, Child<T>)> =
+        a.children().into_iter().cloned().zip(b.children().into_iter().cloned()).collect();
+    while let Some(pair) = pending.pop() {
+        match pair {
+            (None, None) => {}
+            //the very same subtree is equal to itself
+            (Some(a), Some(b)) if Rc::ptr_eq(&a, &b) => {}
+            (Some(a), Some(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                if a.label() != b.label() {
+                    return false;
+                }
+                pending.extend(a.children().into_iter().cloned().zip(b.children().into_iter().cloned()));
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+//pre-order: label, then a marker and the subtree for the left child, then the same for the right one
+fn deep_hash<T: Shape, H: Hasher>(node: &T, state: &mut H) {
+    node.label().hash(state);
+    let [left, right] = node.children();
+    let mut pending = vec![right.clone(), left.clone()];
+    while let Some(child) = pending.pop() {
+        match child {
+            None => state.write_u8(0),
+            Some(child) => {
+                state.write_u8(1);
+                let child = child.borrow();
+                child.label().hash(state);
+                let [left, right] = child.children();
+                pending.push(right.clone());
+                pending.push(left.clone());
+            }
+        }
+    }
+}
+
+/**
+ * Deep structural equality: same value, same left subtree and same right subtree
+ * The parent pointer is not compared, so two equal subtrees can hang below different parents
+ * Since Rc and RefCell forward PartialEq, NodeLink == NodeLink compares whole trees too
+ */
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        deep_eq(self, other)
+    }
+}
+
+impl Eq for Node {}
+
+/**
+ * Consistent with PartialEq, a missing child hashes differently from any present one
+ * so 1 with a lone left 2 and 1 with a lone right 2 don't collide by construction
+ */
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        deep_hash(self, state);
+    }
+}
+
+impl PartialEq for BstNode {
+    fn eq(&self, other: &Self) -> bool {
+        deep_eq(self, other)
+    }
+}
+
+impl Eq for BstNode {}
+
+impl Hash for BstNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        deep_hash(self, state);
+    }
+}
+
+impl Node {
+    /**
+     * Hash of the shape and values below node, equal trees always get the same number
+     */
+    pub fn structural_hash(node: &NodeLink) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        node.borrow().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /**
+     * True if both trees hold the same in-order sequence of values, whatever their shape
+     */
+    pub fn same_values(a: &NodeLink, b: &NodeLink) -> bool {
+        Node::inorder_values(a) == Node::inorder_values(b)
+    }
+
+    /**
+     * True if b can be turned into a by swapping the left and right child of any number of nodes
+     * Whether two children pair up straight or crossed depends on their whole subtrees, so instead of
+     * trying both ways per pair of nodes, every subtree of both trees gets a flip-invariant number
+     * from one shared table, bottom-up and without recursion, and only the two root numbers are compared
+     */
+    pub fn is_flip_isomorphic(a: &NodeLink, b: &NodeLink) -> bool {
+        let mut shapes = HashMap::new();
+        Node::flip_shape(a, &mut shapes) == Node::flip_shape(b, &mut shapes)
+    }
+
+    /**
+     * Number of the shape below root, the same number for the same (value, unordered pair of child shapes)
+     * Nodes are listed in pre-order and numbered backwards, so children are always numbered before their parent
+     */
+    fn flip_shape(root: &NodeLink, shapes: &mut HashMap<(i32, Option<usize>, Option<usize>), usize>) -> usize {
+        let mut nodes: Vec<(i32, [Option<usize>; 2])> = Vec::new();
+        let mut stack: Vec<(NodeLink, Option<(usize, usize)>)> = vec![(root.clone(), None)];
+        while let Some((node, parent)) = stack.pop() {
+            let node = node.borrow();
+            let index = nodes.len();
+            nodes.push((node.value, [None, None]));
+            if let Some((parent, side)) = parent {
+                nodes[parent].1[side] = Some(index);
+            }
+            stack.extend(node.right.clone().map(|right| (right, Some((index, 1)))));
+            stack.extend(node.left.clone().map(|left| (left, Some((index, 0)))));
+        }
+        let mut numbers = vec![0; nodes.len()];
+        for index in (0..nodes.len()).rev() {
+            let (value, children) = nodes[index];
+            let mut children = children.map(|child| child.map(|child| numbers[child]));
+            children.sort();
+            let next = shapes.len();
+            numbers[index] = *shapes.entry((value, children[0], children[1])).or_insert(next);
+        }
+        numbers[0]
+    }
+}
+
+impl BstNode {
+    pub fn structural_hash(node: &BstNodeLink) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        node.borrow().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /**
+     * True if both trees hold the same keys, e.g. the same set inserted in a different order
+     */
+    pub fn same_keys(a: &BstNodeLink, b: &BstNodeLink) -> bool {
+        BstNode::inorder_keys(a) == BstNode::inorder_keys(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structure::bst::{BstNode, BstNodeLink};
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::demo_bst;
+    use crate::tool::{serialize, tree_text};
+
+    #[test]
+    fn test_compare() {
+        let first = demo_bst();
+        let second: BstNodeLink = BstNode::new_bst_nodelink(15);
+        for key in [18, 20, 17, 6, 7, 13, 9, 3, 4, 2] {
+            BstNode::tree_insert(&second, key);
+        }
+        //same insert order per subtree gives the same shape
+        assert_eq!(first, second);
+        assert_eq!(BstNode::structural_hash(&first), BstNode::structural_hash(&second));
+
+        //same keys in another shape
+        let mut rotated = serialize::bst_from_json(&serialize::bst_to_json(&first)).unwrap();
+        let top = rotated.clone();
+        BstNode::rotate_left(&mut rotated, &top);
+        assert_ne!(first, rotated);
+        assert!(BstNode::same_keys(&first, &rotated));
+        assert_ne!(BstNode::structural_hash(&first), BstNode::structural_hash(&rotated));
+        BstNode::tree_insert(&rotated, 1);
+        assert!(!BstNode::same_keys(&first, &rotated));
+
+        //subtrees compare equal regardless of where they hang
+        let six = first.borrow().left.clone().unwrap();
+        assert_eq!(six, BstNode::from_preorder(&[6, 3, 2, 4, 7, 13, 9]).unwrap());
+
+        //tree::Node, left-only and right-only children differ but are flip isomorphic
+        let left_only = tree_text::tree_from_text("Tree Node { P1 -> L2; P2 -> L3; P2 -> R4; }").unwrap();
+        let right_only = tree_text::tree_from_text("Tree Node { P1 -> R2; P2 -> L4; P2 -> R3; }").unwrap();
+        assert_ne!(left_only, right_only);
+        assert_ne!(Node::structural_hash(&left_only), Node::structural_hash(&right_only));
+        assert!(Node::is_flip_isomorphic(&left_only, &right_only));
+        assert!(!Node::same_values(&left_only, &right_only));
+        let copy = tree_text::tree_from_text(&tree_text::tree_to_text(&left_only)).unwrap();
+        assert_eq!(left_only, copy);
+        assert_eq!(Node::structural_hash(&left_only), Node::structural_hash(&copy));
+        let other = tree_text::tree_from_text("Tree Node { P1 -> R2; P2 -> L4; P2 -> R5; }").unwrap();
+        assert!(!Node::is_flip_isomorphic(&left_only, &other));
+        //same values, different shape
+        let chain_of_three = tree_text::tree_from_text("Tree Node { P1 -> L2; P2 -> L3; }").unwrap();
+        let spread = tree_text::tree_from_text("Tree Node { P1 -> L2; P1 -> R3; }").unwrap();
+        assert!(!Node::is_flip_isomorphic(&chain_of_three, &spread));
+        //a deep chain against its mirror doesn't recurse
+        let left_chain: NodeLink = Node::new_nodelink(0);
+        let right_chain: NodeLink = Node::new_nodelink(0);
+        let (mut left_tail, mut right_tail) = (left_chain.clone(), right_chain.clone());
+        for value in 1..200_000 {
+            left_tail.borrow_mut().add_left_child(&left_tail, value);
+            right_tail.borrow_mut().add_right_child(&right_tail, value);
+            let next = left_tail.borrow().left.clone().unwrap();
+            left_tail = next;
+            let next = right_tail.borrow().right.clone().unwrap();
+            right_tail = next;
+        }
+        assert!(Node::is_flip_isomorphic(&left_chain, &left_chain));
+        assert!(Node::is_flip_isomorphic(&left_chain, &right_chain));
+        right_tail.borrow_mut().value = -1;
+        assert!(!Node::is_flip_isomorphic(&left_chain, &right_chain));
+        let mut seen = std::collections::HashSet::new();
+        seen.insert(Node::structural_hash(&left_only));
+        assert!(seen.contains(&Node::structural_hash(&copy)));
+    }
+}
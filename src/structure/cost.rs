@@ -0,0 +1,223 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use std::fs::File;
+use std::io::{Result, Write};
+use std::ops::AddAssign;
+
+/**
+ * Opt-in cost instrumentation for the BstNode operations
+ * Every instrumented operation has a *_counted twin taking the counter to report to, the plain one
+ * passes NoCost, so nothing changes for callers that don't measure
+ * e.g: log.measure("tree_search", 9, &root, |costs| root.borrow().tree_search_counted(&9, costs));
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Costs {
+    //key against key, a three-way compare (cmp) counts once
+    pub comparisons: u64,
+    //moves from a node to its left or right child
+    pub child_hops: u64,
+    //moves from a node to its parent
+    pub parent_hops: u64,
+    //Weak parent pointers turned into Rc
+    pub weak_upgrades: u64,
+    //nodes created, including the copies tree_search / minimum hand out
+    pub allocations: u64,
+    pub rotations: u64,
+}
+
+impl AddAssign for Costs {
+    fn add_assign(&mut self, other: Costs) {
+        self.comparisons += other.comparisons;
+        self.child_hops += other.child_hops;
+        self.parent_hops += other.parent_hops;
+        self.weak_upgrades += other.weak_upgrades;
+        self.allocations += other.allocations;
+        self.rotations += other.rotations;
+    }
+}
+
+/**
+ * What the *_counted operations report, one call per counted step
+ * Every method does nothing by default, so a counter only picks the steps it cares about
+ */
+pub trait CostCounter {
+    fn comparison(&mut self) {}
+    fn child_hop(&mut self) {}
+    fn parent_hop(&mut self) {}
+    fn weak_upgrade(&mut self) {}
+    fn allocation(&mut self) {}
+    fn rotation(&mut self) {}
+}
+
+//the counter of the plain operations, counts nothing
+pub struct NoCost;
+
+impl CostCounter for NoCost {}
+
+impl CostCounter for Costs {
+    fn comparison(&mut self) {
+        self.comparisons += 1;
+    }
+
+    fn child_hop(&mut self) {
+        self.child_hops += 1;
+    }
+
+    fn parent_hop(&mut self) {
+        self.parent_hops += 1;
+    }
+
+    fn weak_upgrade(&mut self) {
+        self.weak_upgrades += 1;
+    }
+
+    fn allocation(&mut self) {
+        self.allocations += 1;
+    }
+
+    fn rotation(&mut self) {
+        self.rotations += 1;
+    }
+}
+
+/**
+ * Costs of one measured call, height is the tree height right before the call
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostRecord {
+    pub operation: String,
+    pub key: i32,
+    pub height: usize,
+    pub costs: Costs,
+}
+
+#[derive(Default)]
+pub struct CostLog {
+    records: Vec<CostRecord>,
+}
+
+impl CostLog {
+    pub fn new() -> CostLog {
+        CostLog::default()
+    }
+
+    /**
+     * Run f with a fresh counter and keep one record with what f reported to it, f's result is handed back
+     * Only what f passes the counter to is counted
+     */
+    pub fn measure<T>(&mut self, operation: &str, key: i32, root: &BstNodeLink, f: impl FnOnce(&mut Costs) -> T) -> T {
+        let height = BstNode::height(root);
+        let mut costs = Costs::default();
+        let result = f(&mut costs);
+        self.records.push(CostRecord {
+            operation: operation.to_string(),
+            key,
+            height,
+            costs,
+        });
+        result
+    }
+
+    pub fn records(&self) -> &[CostRecord] {
+        &self.records
+    }
+
+    //sum over every record of operation
+    pub fn total(&self, operation: &str) -> Costs {
+        let mut total = Costs::default();
+        for record in self.records.iter().filter(|record| record.operation == operation) {
+            total += record.costs;
+        }
+        total
+    }
+
+    /**
+     * One line per record, e.g:
+     * operation,key,height,comparisons,child_hops,parent_hops,weak_upgrades,allocations,rotations
+     * tree_search,9,4,9,4,0,0,1,0
+     */
+    pub fn to_csv(&self) -> String {
+        let mut csv = "operation,key,height,comparisons,child_hops,parent_hops,weak_upgrades,allocations,rotations\n".to_string();
+        for record in &self.records {
+            let costs = &record.costs;
+            csv += &format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                record.operation,
+                record.key,
+                record.height,
+                costs.comparisons,
+                costs.child_hops,
+                costs.parent_hops,
+                costs.weak_upgrades,
+                costs.allocations,
+                costs.rotations
+            );
+        }
+        csv
+    }
+
+    pub fn write_csv(&self, output_path: &str) -> Result<()> {
+        let mut output = File::create(output_path)?;
+        output.write_all(self.to_csv().as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CostLog, Costs};
+    use crate::structure::bst::BstNode;
+    use crate::test_util::{demo_bst, temp_path};
+    use crate::tool::bench;
+
+    #[test]
+    fn test_costs() {
+        let rootlink = demo_bst();
+        let mut log = CostLog::new();
+        //15 -> 6 -> 7 -> 13 -> 9: five equality checks, four direction checks, four hops, one copy
+        let found = log.measure("tree_search", 9, &rootlink, |costs| rootlink.borrow().tree_search_counted(&9, costs)).unwrap();
+        let search = log.records()[0].clone();
+        assert_eq!(search.height, 4);
+        assert_eq!(
+            search.costs,
+            Costs { comparisons: 9, child_hops: 4, parent_hops: 0, weak_upgrades: 0, allocations: 1, rotations: 0 }
+        );
+        //9 has no right child: up to 13 where it is the left child
+        log.measure("tree_successor", 9, &rootlink, |costs| BstNode::tree_successor_counted(&found, costs));
+        assert_eq!(log.records()[1].costs.weak_upgrades, 1);
+        assert_eq!(log.records()[1].costs.comparisons, 1);
+        log.measure("tree_insert", 5, &rootlink, |costs| BstNode::tree_insert_counted(&rootlink, 5, costs));
+        let insert = &log.records()[2].costs;
+        assert_eq!((insert.comparisons, insert.child_hops, insert.allocations), (4, 3, 1));
+        let fifteen = rootlink.borrow().tree_search(&15).unwrap();
+        log.measure("tree_delete", 15, &rootlink, |costs| BstNode::tree_delete_counted(&mut rootlink.clone(), &fifteen, costs));
+        assert!(log.records()[3].costs.weak_upgrades > 0);
+        let mut root = rootlink.clone();
+        log.measure("rotate_left", 17, &rootlink, |costs| BstNode::rotate_left_counted(&mut root, &rootlink, costs));
+        assert_eq!(log.records()[4].costs.rotations, 1);
+        //the plain operations count nothing, a counter can be kept across calls without a log
+        BstNode::tree_insert(&root, 1);
+        assert_eq!(log.records().len(), 5);
+        let mut costs = Costs::default();
+        root.borrow().tree_search_counted(&3, &mut costs);
+        root.borrow().maximum_counted(&mut costs);
+        assert_eq!((costs.allocations, costs.child_hops), (2, 4));
+        log.measure("outer", 0, &root, |outer| *outer += costs);
+        assert_eq!(log.records()[5].costs, costs);
+        assert_eq!(log.total("outer"), costs);
+        assert_eq!(log.total("tree_search").child_hops, 4);
+
+        let csv = log.to_csv();
+        assert!(csv.starts_with("operation,key,height,comparisons,child_hops,parent_hops,weak_upgrades,allocations,rotations\n"));
+        assert!(csv.contains("\ntree_search,9,4,9,4,0,0,1,0\n"));
+        assert_eq!(csv.lines().count(), 7);
+
+        //on a chain the search cost grows with the height, on a random tree it stays near log n
+        let chain = bench::collect_costs(&(0..200).collect::<Vec<i32>>());
+        let deepest = chain.records().iter().filter(|record| record.operation == "tree_search").map(|record| record.costs.child_hops).max();
+        assert_eq!(deepest, Some(199));
+        let dir = temp_path("costs_test");
+        bench::run_costs(100, dir.to_str().unwrap()).unwrap();
+        let random = std::fs::read_to_string(dir.join("costs_random.csv")).unwrap();
+        assert!(random.lines().count() > 300);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
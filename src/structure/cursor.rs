@@ -0,0 +1,262 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+
+/**
+ * A position inside a BST that can walk in key order and edit in place
+ * Moving uses the parent pointers (tree_successor / tree_predecessor), so a step costs O(h) at worst
+ * and O(1) amortized over a full walk, no fresh tree_search from the root is needed
+ * Walking past either end leaves the cursor on an empty position (key() is None),
+ * from there move_next starts again at the lowest key and move_prev at the highest
+ */
+pub struct BstCursor {
+    root: BstNodeLink,
+    current: Option<BstNodeLink>,
+}
+
+impl BstCursor {
+    /**
+     * Cursor positioned at node, node may also be a copy returned by tree_search
+     */
+    pub fn new(root: &BstNodeLink, node: &BstNodeLink) -> BstCursor {
+        let current = node.borrow().key.map(|_| BstNode::resolve_link(root, node));
+        BstCursor {
+            root: root.clone(),
+            current,
+        }
+    }
+
+    //cursor positioned at the lowest key, empty position for an empty tree
+    pub fn front(root: &BstNodeLink) -> BstCursor {
+        let mut cursor = BstCursor {
+            root: root.clone(),
+            current: None,
+        };
+        cursor.move_next();
+        cursor
+    }
+
+    //cursor positioned at the highest key, empty position for an empty tree
+    pub fn back(root: &BstNodeLink) -> BstCursor {
+        let mut cursor = BstCursor {
+            root: root.clone(),
+            current: None,
+        };
+        cursor.move_prev();
+        cursor
+    }
+
+    pub fn key(&self) -> Option<i32> {
+        self.current.as_ref().and_then(|node| node.borrow().key)
+    }
+
+    /**
+     * The node the cursor stands on, as stored in the tree (not a copy)
+     */
+    pub fn current(&self) -> Option<BstNodeLink> {
+        self.current.clone()
+    }
+
+    //successor and predecessor hand out copies for nodes found by descending, keep the stored link instead
+    fn settle(&mut self, node: Option<BstNodeLink>) {
+        self.current = node.map(|node| BstNode::resolve_link(&self.root, &node));
+    }
+
+    pub fn move_next(&mut self) {
+        let next = match &self.current {
+            Some(node) => BstNode::tree_successor(node),
+            None if self.root.borrow().key.is_some() => Some(self.root.borrow().minimum()),
+            None => None,
+        };
+        self.settle(next);
+    }
+
+    pub fn move_prev(&mut self) {
+        let prev = match &self.current {
+            Some(node) => BstNode::tree_predecessor(node),
+            None if self.root.borrow().key.is_some() => Some(self.root.borrow().maximum()),
+            None => None,
+        };
+        self.settle(prev);
+    }
+
+    /**
+     * Move up one level, the root moves to the empty position
+     */
+    pub fn move_parent(&mut self) {
+        self.current = self
+            .current
+            .as_ref()
+            .and_then(|node| BstNode::upgrade_weak_to_strong(node.borrow().parent.clone()));
+    }
+
+    /**
+     * Delete the current node with tree_delete and move to its successor
+     * Return the removed key, None when the cursor is on the empty position
+     */
+    pub fn remove_current(&mut self) -> Option<i32> {
+        let current = self.current.take()?;
+        let removed = current.borrow().key;
+        let next_key = BstNode::tree_successor(&current).and_then(|node| node.borrow().key);
+        let mut root = self.root.clone();
+        BstNode::tree_delete(&mut root, &current);
+        //tree_delete may move the successor's key into another node (e.g. the root), find it again
+        self.current = next_key.and_then(|key| self.find(key));
+        removed
+    }
+
+    /**
+     * Insert key right after the current position, the cursor doesn't move
+     * The current node is used as a hint: if key falls between it and its successor
+     * the new node is hung directly below one of them, otherwise fall back to tree_insert from the root
+     * Return false if the key already exists
+     */
+    pub fn insert_after(&mut self, key: i32) -> bool {
+        let Some(current) = self.current.clone() else {
+            return self.insert_from_root(key);
+        };
+        let current_key = current.borrow().key.unwrap();
+        let successor = BstNode::tree_successor(&current).map(|node| BstNode::resolve_link(&self.root, &node));
+        let fits = key > current_key
+            && successor
+                .as_ref()
+                .is_none_or(|next| key < next.borrow().key.unwrap());
+        if !fits {
+            return self.insert_from_root(key);
+        }
+        //between current and its successor there is a free slot: current.right or successor.left
+        if current.borrow().right.is_none() {
+            current.borrow_mut().add_right_child(&current, key);
+        } else {
+            let successor = successor.unwrap();
+            successor.borrow_mut().add_left_child(&successor, key);
+        }
+        true
+    }
+
+    /**
+     * Insert key right before the current position, mirror of insert_after
+     */
+    pub fn insert_before(&mut self, key: i32) -> bool {
+        let Some(current) = self.current.clone() else {
+            return self.insert_from_root(key);
+        };
+        let current_key = current.borrow().key.unwrap();
+        let predecessor = BstNode::tree_predecessor(&current).map(|node| BstNode::resolve_link(&self.root, &node));
+        let fits = key < current_key
+            && predecessor
+                .as_ref()
+                .is_none_or(|prev| key > prev.borrow().key.unwrap());
+        if !fits {
+            return self.insert_from_root(key);
+        }
+        if current.borrow().left.is_none() {
+            current.borrow_mut().add_left_child(&current, key);
+        } else {
+            let predecessor = predecessor.unwrap();
+            predecessor.borrow_mut().add_right_child(&predecessor, key);
+        }
+        true
+    }
+
+    //the hint didn't help, a key strictly between two neighbours can't be a duplicate but anything else can
+    fn insert_from_root(&mut self, key: i32) -> bool {
+        if self.find(key).is_some() {
+            return false;
+        }
+        BstNode::tree_insert(&self.root, key);
+        true
+    }
+
+    //stored link of key, descending from the root
+    fn find(&self, key: i32) -> Option<BstNodeLink> {
+        let mut node = Some(self.root.clone());
+        while let Some(current) = node {
+            let current_key = current.borrow().key?;
+            node = if key == current_key {
+                return Some(current);
+            } else if key < current_key {
+                current.borrow().left.clone()
+            } else {
+                current.borrow().right.clone()
+            };
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BstCursor;
+    use crate::structure::bst::BstNode;
+    use crate::test_util::demo_bst;
+
+    #[test]
+    fn test_cursor() {
+        let rootlink = demo_bst();
+        let sorted = BstNode::inorder_keys(&rootlink);
+
+        //full walk both ways
+        let mut cursor = BstCursor::front(&rootlink);
+        let mut walked = Vec::new();
+        while let Some(key) = cursor.key() {
+            walked.push(key);
+            cursor.move_next();
+        }
+        assert_eq!(walked, sorted);
+        //from past the end, stepping back lands on the highest key
+        cursor.move_prev();
+        assert_eq!(cursor.key(), Some(20));
+        let mut cursor = BstCursor::back(&rootlink);
+        walked.clear();
+        while let Some(key) = cursor.key() {
+            walked.push(key);
+            cursor.move_prev();
+        }
+        walked.reverse();
+        assert_eq!(walked, sorted);
+
+        //start from a tree_search copy and climb
+        let nine = rootlink.borrow().tree_search(&9).unwrap();
+        let mut cursor = BstCursor::new(&rootlink, &nine);
+        cursor.move_parent();
+        assert_eq!(cursor.key(), Some(13));
+        cursor.move_prev();
+        assert_eq!(cursor.key(), Some(9));
+        cursor.move_prev();
+        assert_eq!(cursor.key(), Some(7));
+
+        //remove leaves, inner nodes and the root while walking
+        let mut cursor = BstCursor::new(&rootlink, &rootlink.borrow().tree_search(&6).unwrap());
+        assert_eq!(cursor.remove_current(), Some(6));
+        assert_eq!(cursor.key(), Some(7));
+        assert_eq!(cursor.remove_current(), Some(7));
+        assert_eq!(cursor.key(), Some(9));
+        let mut cursor = BstCursor::new(&rootlink, &rootlink);
+        assert_eq!(cursor.remove_current(), Some(15));
+        assert_eq!(cursor.key(), Some(17));
+        assert!(std::rc::Rc::ptr_eq(&cursor.current().unwrap(), &rootlink));
+        assert_eq!(BstNode::inorder_keys(&rootlink), vec![2, 3, 4, 9, 13, 17, 18, 20]);
+        let four = rootlink.borrow().tree_search(&4).unwrap();
+        let four_parent = BstNode::upgrade_weak_to_strong(four.borrow().parent.clone()).unwrap();
+        assert_eq!(four_parent.borrow().key, Some(3));
+
+        //insert next to the cursor, hint used or not
+        let mut cursor = BstCursor::new(&rootlink, &rootlink.borrow().tree_search(&4).unwrap());
+        assert!(cursor.insert_after(5));
+        assert!(cursor.insert_before(1));
+        assert!(cursor.insert_after(19));
+        assert!(!cursor.insert_after(13));
+        assert_eq!(cursor.key(), Some(4));
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(5));
+        assert_eq!(BstNode::inorder_keys(&rootlink), vec![1, 2, 3, 4, 5, 9, 13, 17, 18, 19, 20]);
+
+        //drain everything from the front
+        let mut cursor = BstCursor::front(&rootlink);
+        let mut removed = Vec::new();
+        while let Some(key) = cursor.remove_current() {
+            removed.push(key);
+        }
+        assert_eq!(removed, vec![1, 2, 3, 4, 5, 9, 13, 17, 18, 19, 20]);
+        assert!(rootlink.borrow().key.is_none());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+
+/**
+ * View into a single key of the tree, found with one descent from the root
+ * A vacant entry remembers the node it stopped at, so inserting doesn't walk the tree again
+ * e.g: BstNode::entry(&root, 9).and_modify(|node| ...).or_insert();
+ * The closures of and_modify and or_insert_with only get to read the node, a changed key would break the ordering
+ */
+pub enum Entry {
+    Occupied(OccupiedEntry),
+    Vacant(VacantEntry),
+}
+
+pub struct OccupiedEntry {
+    root: BstNodeLink,
+    node: BstNodeLink,
+}
+
+//parent is None only for an empty root (key None), the key then goes into the root itself
+pub struct VacantEntry {
+    root: BstNodeLink,
+    key: i32,
+    parent: Option<BstNodeLink>,
+}
+
+impl BstNode {
+    pub fn entry(root: &BstNodeLink, key: i32) -> Entry {
+        let mut current = root.clone();
+        loop {
+            let current_key = match current.borrow().key {
+                Some(current_key) => current_key,
+                None => {
+                    return Entry::Vacant(VacantEntry {
+                        root: root.clone(),
+                        key,
+                        parent: None,
+                    })
+                }
+            };
+            if key == current_key {
+                return Entry::Occupied(OccupiedEntry {
+                    root: root.clone(),
+                    node: current,
+                });
+            }
+            let next = if key < current_key {
+                current.borrow().left.clone()
+            } else {
+                current.borrow().right.clone()
+            };
+            match next {
+                Some(next) => current = next,
+                None => {
+                    return Entry::Vacant(VacantEntry {
+                        root: root.clone(),
+                        key,
+                        parent: Some(current),
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl Entry {
+    pub fn key(&self) -> i32 {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /**
+     * Stored node of the key, inserted first if it was missing
+     */
+    pub fn or_insert(self) -> BstNodeLink {
+        match self {
+            Entry::Occupied(entry) => entry.get(),
+            Entry::Vacant(entry) => entry.insert(),
+        }
+    }
+
+    /**
+     * Same as or_insert, f only runs on the freshly inserted node
+     * Unlike std's or_insert_with, f doesn't build a value: the tree stores keys only and the key is already
+     * known, so f just gets to look at the new node, e.g: to count or log insertions
+     */
+    pub fn or_insert_with<F: FnOnce(&BstNode)>(self, f: F) -> BstNodeLink {
+        match self {
+            Entry::Occupied(entry) => entry.get(),
+            Entry::Vacant(entry) => {
+                let node = entry.insert();
+                f(&node.borrow());
+                node
+            }
+        }
+    }
+
+    /**
+     * Run f on the stored node if the key is present, then hand the entry back for chaining
+     * f gets the node read-only, with no values in the tree there is nothing it could change without moving the key
+     */
+    pub fn and_modify<F: FnOnce(&BstNode)>(self, f: F) -> Entry {
+        if let Entry::Occupied(entry) = &self {
+            f(&entry.node.borrow());
+        }
+        self
+    }
+}
+
+impl OccupiedEntry {
+    pub fn key(&self) -> i32 {
+        self.node.borrow().key.unwrap()
+    }
+
+    //the node as stored in the tree, not a copy
+    pub fn get(&self) -> BstNodeLink {
+        self.node.clone()
+    }
+
+    /**
+     * Delete the key with tree_delete and return it
+     */
+    pub fn remove(self) -> i32 {
+        let key = self.key();
+        let mut root = self.root.clone();
+        BstNode::tree_delete(&mut root, &self.node);
+        key
+    }
+}
+
+impl VacantEntry {
+    pub fn key(&self) -> i32 {
+        self.key
+    }
+
+    /**
+     * Hang the key below the node the descent stopped at and return the new node
+     */
+    pub fn insert(self) -> BstNodeLink {
+        let parent = match self.parent {
+            Some(parent) => parent,
+            None => {
+                self.root.borrow_mut().key = Some(self.key);
+                return self.root;
+            }
+        };
+        let go_left = self.key < parent.borrow().key.unwrap();
+        let mut parent_mut = parent.borrow_mut();
+        if go_left {
+            parent_mut.add_left_child(&parent, self.key);
+            parent_mut.left.clone().unwrap()
+        } else {
+            parent_mut.add_right_child(&parent, self.key);
+            parent_mut.right.clone().unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Entry;
+    use crate::structure::bst::BstNode;
+    use crate::test_util::demo_bst;
+
+    #[test]
+    fn test_entry() {
+        let rootlink = demo_bst();
+
+        //vacant: inserted right below the node the descent stopped at
+        let mut created = Vec::new();
+        let mut modified = Vec::new();
+        assert_eq!(BstNode::entry(&rootlink, 10).key(), 10);
+        let ten = BstNode::entry(&rootlink, 10)
+            .and_modify(|node| modified.push(node.key.unwrap()))
+            .or_insert_with(|node| created.push(node.key.unwrap()));
+        assert_eq!(ten.borrow().key, Some(10));
+        let ten_parent = BstNode::upgrade_weak_to_strong(ten.borrow().parent.clone()).unwrap();
+        assert_eq!(ten_parent.borrow().key, Some(9));
+        assert!(std::rc::Rc::ptr_eq(ten_parent.borrow().right.as_ref().unwrap(), &ten));
+
+        //occupied: same node handed back, or_insert_with doesn't run
+        let again = BstNode::entry(&rootlink, 10)
+            .and_modify(|node| modified.push(node.key.unwrap()))
+            .or_insert_with(|node| created.push(node.key.unwrap()));
+        assert!(std::rc::Rc::ptr_eq(&again, &ten));
+        assert_eq!(created, vec![10]);
+        assert_eq!(modified, vec![10]);
+        assert_eq!(BstNode::entry(&rootlink, 1).or_insert().borrow().key, Some(1));
+        assert_eq!(BstNode::inorder_keys(&rootlink), vec![1, 2, 3, 4, 6, 7, 9, 10, 13, 15, 17, 18, 20]);
+
+        match BstNode::entry(&rootlink, 6) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 6),
+            Entry::Vacant(_) => panic!("6 should be in the tree"),
+        }
+        match BstNode::entry(&rootlink, 6) {
+            Entry::Occupied(_) => panic!("6 was removed"),
+            Entry::Vacant(entry) => assert_eq!(entry.key(), 6),
+        }
+
+        //an empty root takes the key itself
+        let empty = BstNode::new_bst_nodelink(0);
+        empty.borrow_mut().key = None;
+        let first = BstNode::entry(&empty, 42).or_insert();
+        assert!(std::rc::Rc::ptr_eq(&first, &empty));
+        assert_eq!(empty.borrow().key, Some(42));
+    }
+}
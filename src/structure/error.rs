@@ -0,0 +1,330 @@
+use crate::structure::bst::{BstNode, BstNodeLink, WeakBstNodeLink};
+use std::cell::{Ref, RefMut};
+use std::fmt;
+use std::io;
+
+/**
+ * Everything that makes the plain BstNode operations panic, as a value the caller can recover from
+ */
+#[derive(Debug)]
+pub enum BstError {
+    //no node holds the key
+    KeyNotFound(i32),
+    //the key is already in the tree
+    DuplicateKey(i32),
+    //the root (or a node that should hold a key) has key None
+    EmptyTree,
+    //a Weak parent pointer outlived its parent
+    DanglingParent,
+    //a node is already borrowed somewhere else, e.g. a RefMut still alive in the caller
+    BorrowConflict,
+    //writing or reading a file failed
+    Io(io::Error),
+}
+
+impl fmt::Display for BstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BstError::KeyNotFound(key) => write!(f, "key {} not found", key),
+            BstError::DuplicateKey(key) => write!(f, "key {} already exists", key),
+            BstError::EmptyTree => write!(f, "tree is empty"),
+            BstError::DanglingParent => write!(f, "parent pointer refers to a dropped node"),
+            BstError::BorrowConflict => write!(f, "node is already borrowed"),
+            BstError::Io(error) => write!(f, "io error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for BstError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BstError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BstError {
+    fn from(error: io::Error) -> Self {
+        BstError::Io(error)
+    }
+}
+
+//RefCell::borrow without the panic
+pub fn try_read(node: &BstNodeLink) -> Result<Ref<'_, BstNode>, BstError> {
+    node.try_borrow().map_err(|_| BstError::BorrowConflict)
+}
+
+pub fn try_write(node: &BstNodeLink) -> Result<RefMut<'_, BstNode>, BstError> {
+    node.try_borrow_mut().map_err(|_| BstError::BorrowConflict)
+}
+
+fn try_key(node: &BstNodeLink) -> Result<i32, BstError> {
+    try_read(node)?.key.ok_or(BstError::EmptyTree)
+}
+
+/**
+ * Fallible twins of the operations in bst.rs, they never panic and never leave the tree half modified
+ * Every node is borrowed with try_borrow, parents are upgraded with a check, empty keys are reported
+ * The nodes handed back are the ones stored in the tree, not copies
+ */
+impl BstNode {
+    pub fn try_upgrade_weak_to_strong(node: Option<WeakBstNodeLink>) -> Result<Option<BstNodeLink>, BstError> {
+        match node {
+            None => Ok(None),
+            Some(x) => x.upgrade().map(Some).ok_or(BstError::DanglingParent),
+        }
+    }
+
+    fn try_parent(node: &BstNodeLink) -> Result<Option<BstNodeLink>, BstError> {
+        let parent = try_read(node)?.parent.clone();
+        BstNode::try_upgrade_weak_to_strong(parent)
+    }
+
+    pub fn try_get_root(node: &BstNodeLink) -> Result<BstNodeLink, BstError> {
+        let mut current = node.clone();
+        while let Some(parent) = BstNode::try_parent(&current)? {
+            current = parent;
+        }
+        Ok(current)
+    }
+
+    pub fn try_tree_search(root: &BstNodeLink, value: i32) -> Result<BstNodeLink, BstError> {
+        let mut current = root.clone();
+        loop {
+            let key = try_key(&current)?;
+            if key == value {
+                return Ok(current);
+            }
+            let next = {
+                let node = try_read(&current)?;
+                if value < key {
+                    node.left.clone()
+                } else {
+                    node.right.clone()
+                }
+            };
+            current = next.ok_or(BstError::KeyNotFound(value))?;
+        }
+    }
+
+    pub fn try_minimum(node: &BstNodeLink) -> Result<BstNodeLink, BstError> {
+        try_key(node)?;
+        let mut current = node.clone();
+        loop {
+            let left = try_read(&current)?.left.clone();
+            match left {
+                Some(left) => current = left,
+                None => return Ok(current),
+            }
+        }
+    }
+
+    pub fn try_maximum(node: &BstNodeLink) -> Result<BstNodeLink, BstError> {
+        try_key(node)?;
+        let mut current = node.clone();
+        loop {
+            let right = try_read(&current)?.right.clone();
+            match right {
+                Some(right) => current = right,
+                None => return Ok(current),
+            }
+        }
+    }
+
+    /**
+     * Ok(None) if x_node holds the highest key
+     */
+    pub fn try_tree_successor(x_node: &BstNodeLink) -> Result<Option<BstNodeLink>, BstError> {
+        let right = try_read(x_node)?.right.clone();
+        if let Some(right) = right {
+            return BstNode::try_minimum(&right).map(Some);
+        }
+        let mut key = try_key(x_node)?;
+        let mut current = BstNode::try_parent(x_node)?;
+        while let Some(parent) = current {
+            let came_from_left = match &try_read(&parent)?.left {
+                Some(left) => try_key(left)? == key,
+                None => false,
+            };
+            if came_from_left {
+                return Ok(Some(parent));
+            }
+            key = try_key(&parent)?;
+            current = BstNode::try_parent(&parent)?;
+        }
+        Ok(None)
+    }
+
+    /**
+     * Ok(None) if x_node holds the lowest key
+     */
+    pub fn try_tree_predecessor(x_node: &BstNodeLink) -> Result<Option<BstNodeLink>, BstError> {
+        let left = try_read(x_node)?.left.clone();
+        if let Some(left) = left {
+            return BstNode::try_maximum(&left).map(Some);
+        }
+        let mut key = try_key(x_node)?;
+        let mut current = BstNode::try_parent(x_node)?;
+        while let Some(parent) = current {
+            let came_from_right = match &try_read(&parent)?.right {
+                Some(right) => try_key(right)? == key,
+                None => false,
+            };
+            if came_from_right {
+                return Ok(Some(parent));
+            }
+            key = try_key(&parent)?;
+            current = BstNode::try_parent(&parent)?;
+        }
+        Ok(None)
+    }
+
+    /**
+     * Insert value and return the new node, an empty root takes the value itself
+     * Unlike tree_insert, a duplicate is reported instead of silently ignored
+     */
+    pub fn try_tree_insert(root: &BstNodeLink, value: i32) -> Result<BstNodeLink, BstError> {
+        if try_read(root)?.key.is_none() {
+            try_write(root)?.key = Some(value);
+            return Ok(root.clone());
+        }
+        let mut current = root.clone();
+        loop {
+            let key = try_key(&current)?;
+            if key == value {
+                return Err(BstError::DuplicateKey(value));
+            }
+            let next = {
+                let node = try_read(&current)?;
+                if value < key {
+                    node.left.clone()
+                } else {
+                    node.right.clone()
+                }
+            };
+            match next {
+                Some(next) => current = next,
+                None => {
+                    let mut node = try_write(&current)?;
+                    if value < key {
+                        node.add_left_child(&current, value);
+                        return Ok(node.left.clone().unwrap());
+                    }
+                    node.add_right_child(&current, value);
+                    return Ok(node.right.clone().unwrap());
+                }
+            }
+        }
+    }
+
+    /**
+     * Delete the node holding z's key, z may be a copy from tree_search
+     * All the nodes tree_delete is going to touch are checked first, so on error the tree is unchanged
+     */
+    pub fn try_tree_delete(root: &mut BstNodeLink, z: &BstNodeLink) -> Result<(), BstError> {
+        let key = try_key(z)?;
+        try_key(root)?;
+        let stored = BstNode::try_tree_search(root, key)?;
+        let (left, right) = {
+            let node = try_read(&stored)?;
+            (node.left.clone(), node.right.clone())
+        };
+        let mut touched = vec![root.clone(), stored.clone()];
+        touched.extend(BstNode::try_parent(&stored)?);
+        //when the root is replaced its new children get their parent rewritten too
+        for child in [&left, &right].into_iter().flatten() {
+            let child_ref = try_read(child)?;
+            touched.extend(child_ref.left.clone());
+            touched.extend(child_ref.right.clone());
+            touched.push(child.clone());
+        }
+        if left.is_some() && right.is_some() {
+            let successor = BstNode::try_tree_successor(&stored)?.ok_or(BstError::KeyNotFound(key))?;
+            touched.extend(BstNode::try_parent(&successor)?);
+            touched.extend(try_read(&successor)?.right.clone());
+            touched.push(successor);
+        }
+        for node in &touched {
+            try_write(node)?;
+        }
+        BstNode::tree_delete(root, &stored);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BstError;
+    use crate::structure::bst::{BstNode, BstNodeLink};
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::temp_path;
+    use crate::tool::dot_reader;
+
+    #[test]
+    fn test_fallible() {
+        let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
+        for key in [6, 18, 17, 20, 3, 7, 2, 4, 13, 9] {
+            BstNode::try_tree_insert(&rootlink, key).unwrap();
+        }
+        assert!(matches!(BstNode::try_tree_insert(&rootlink, 7), Err(BstError::DuplicateKey(7))));
+        assert!(matches!(BstNode::try_tree_search(&rootlink, 8), Err(BstError::KeyNotFound(8))));
+        let nine = BstNode::try_tree_search(&rootlink, 9).unwrap();
+        assert_eq!(BstNode::try_tree_successor(&nine).unwrap().unwrap().borrow().key, Some(13));
+        assert_eq!(BstNode::try_tree_predecessor(&nine).unwrap().unwrap().borrow().key, Some(7));
+        assert!(BstNode::try_tree_successor(&BstNode::try_maximum(&rootlink).unwrap()).unwrap().is_none());
+        assert!(BstNode::try_tree_predecessor(&BstNode::try_minimum(&rootlink).unwrap()).unwrap().is_none());
+        assert!(std::rc::Rc::ptr_eq(&BstNode::try_get_root(&nine).unwrap(), &rootlink));
+
+        //a node borrowed by the caller is reported instead of panicking, and the tree is left alone
+        {
+            let _held = nine.borrow_mut();
+            assert!(matches!(BstNode::try_tree_insert(&rootlink, 10), Err(BstError::BorrowConflict)));
+            assert!(matches!(
+                BstNode::try_tree_delete(&mut rootlink.clone(), &BstNode::try_tree_search(&rootlink, 13).unwrap()),
+                Err(BstError::BorrowConflict)
+            ));
+            let dot_path = temp_path("fallible_test.dot");
+            let result = crate::tool::try_generate_dotfile_bst(&rootlink, dot_path.to_str().unwrap());
+            assert!(matches!(result, Err(BstError::BorrowConflict)));
+        }
+        assert_eq!(BstNode::inorder_keys(&rootlink), vec![2, 3, 4, 6, 7, 9, 13, 15, 17, 18, 20]);
+        let thirteen = rootlink.borrow().tree_search(&13).unwrap();
+        BstNode::try_tree_delete(&mut rootlink.clone(), &thirteen).unwrap();
+        BstNode::try_tree_delete(&mut rootlink.clone(), &rootlink.clone()).unwrap();
+        assert_eq!(BstNode::inorder_keys(&rootlink), vec![2, 3, 4, 6, 7, 9, 17, 18, 20]);
+        let missing = BstNode::new_bst_nodelink(100);
+        assert!(matches!(BstNode::try_tree_delete(&mut rootlink.clone(), &missing), Err(BstError::KeyNotFound(100))));
+
+        //a parent that was dropped while the child is still held
+        let orphan = {
+            let parent = BstNode::new_bst_nodelink(1);
+            BstNode::tree_insert(&parent, 2);
+            let child = parent.borrow().right.clone().unwrap();
+            child
+        };
+        assert!(matches!(BstNode::try_get_root(&orphan), Err(BstError::DanglingParent)));
+        assert!(matches!(BstNode::try_tree_successor(&orphan), Err(BstError::DanglingParent)));
+
+        let empty = BstNode::new_bst_nodelink(0);
+        empty.borrow_mut().key = None;
+        assert!(matches!(BstNode::try_minimum(&empty), Err(BstError::EmptyTree)));
+        assert!(matches!(BstNode::try_tree_search(&empty, 1), Err(BstError::EmptyTree)));
+        assert!(std::rc::Rc::ptr_eq(&BstNode::try_tree_insert(&empty, 1).unwrap(), &empty));
+
+        let bad_path = temp_path("missing_dir").join("out.dot");
+        let result = crate::tool::try_generate_dotfile_bst(&rootlink, bad_path.to_str().unwrap());
+        assert!(matches!(result, Err(BstError::Io(_))));
+        let treelink: NodeLink = Node::new_nodelink(5);
+        treelink.borrow_mut().add_left_child(&treelink, 3);
+        let result = crate::tool::try_generate_dotfile(&treelink, bad_path.to_str().unwrap());
+        assert!(matches!(result, Err(BstError::Io(_))));
+        let dot_path = temp_path("fallible_test.dot");
+        crate::tool::try_generate_dotfile(&treelink, dot_path.to_str().unwrap()).unwrap();
+        crate::tool::try_generate_dotfile_bst(&rootlink, dot_path.to_str().unwrap()).unwrap();
+        let restored = dot_reader::read_dotfile_bst(dot_path.to_str().unwrap()).unwrap();
+        let _ = std::fs::remove_file(&dot_path);
+        assert_eq!(restored, rootlink);
+        assert!(BstError::KeyNotFound(3).to_string().contains('3'));
+    }
+}
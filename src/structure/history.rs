@@ -0,0 +1,315 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use std::rc::Rc;
+
+/**
+ * A BST whose tree_insert / tree_delete calls are recorded, so they can be undone and redone
+ * Undoing a delete puts the exact previous shape back (not just the key), so a dot export taken
+ * before the delete and one taken after its undo are identical
+ * All mutations must go through the history, a change made behind its back can't be undone
+ * e.g: let mut history = BstHistory::new(&root); history.delete(15); history.undo();
+ */
+pub struct BstHistory {
+    root: BstNodeLink,
+    done: Vec<Action>,
+    undone: Vec<Action>,
+    //name and the number of done actions when the checkpoint was taken
+    checkpoints: Vec<(String, usize)>,
+}
+
+enum Action {
+    Insert(i32),
+    //the deleted node's subtree in pre-order (it fixes the shape), and where that subtree hangs
+    Delete { key: i32, parent: Option<i32>, left_side: bool, subtree: Vec<i32> },
+}
+
+impl Action {
+    fn describe(&self) -> String {
+        match self {
+            Action::Insert(key) => format!("insert {}", key),
+            Action::Delete { key, .. } => format!("delete {}", key),
+        }
+    }
+}
+
+impl BstHistory {
+    pub fn new(root: &BstNodeLink) -> BstHistory {
+        BstHistory {
+            root: root.clone(),
+            done: Vec::new(),
+            undone: Vec::new(),
+            checkpoints: Vec::new(),
+        }
+    }
+
+    pub fn root(&self) -> BstNodeLink {
+        self.root.clone()
+    }
+
+    /**
+     * tree_insert that is recorded, false (and nothing recorded) if the key already exists
+     */
+    pub fn insert(&mut self, key: i32) -> bool {
+        let Some(action) = self.apply_insert(key) else {
+            return false;
+        };
+        self.record(action);
+        true
+    }
+
+    /**
+     * tree_delete that is recorded, false (and nothing recorded) if the key doesn't exist
+     */
+    pub fn delete(&mut self, key: i32) -> bool {
+        let Some(action) = self.apply_delete(key) else {
+            return false;
+        };
+        self.record(action);
+        true
+    }
+
+    //a new action makes the undone ones (and checkpoints taken among them) unreachable
+    fn record(&mut self, action: Action) {
+        self.done.push(action);
+        self.undone.clear();
+        let reachable = self.done.len() - 1;
+        self.checkpoints.retain(|(_, position)| *position <= reachable);
+    }
+
+    fn apply_insert(&mut self, key: i32) -> Option<Action> {
+        if self.root.borrow().tree_search(&key).is_some() {
+            return None;
+        }
+        BstNode::tree_insert(&self.root, key);
+        Some(Action::Insert(key))
+    }
+
+    fn apply_delete(&mut self, key: i32) -> Option<Action> {
+        let found = self.root.borrow().tree_search(&key)?;
+        let stored = BstNode::resolve_link(&self.root, &found);
+        //tree_delete only rearranges the subtree below the deleted node, remember that subtree
+        let subtree = BstNode::preorder_keys(&stored);
+        let parent = BstNode::upgrade_weak_to_strong(stored.borrow().parent.clone());
+        let left_side = parent
+            .as_ref()
+            .is_some_and(|parent| parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(left, &stored)));
+        let parent = parent.and_then(|parent| parent.borrow().key);
+        BstNode::tree_delete(&mut self.root, &found);
+        Some(Action::Delete { key, parent, left_side, subtree })
+    }
+
+    /**
+     * Revert the latest action, false if there is nothing to undo
+     */
+    pub fn undo(&mut self) -> bool {
+        let Some(action) = self.done.pop() else {
+            return false;
+        };
+        match &action {
+            Action::Insert(key) => self.remove_leaf(*key),
+            Action::Delete { parent, left_side, subtree, .. } => self.restore_subtree(*parent, *left_side, subtree),
+        }
+        self.undone.push(action);
+        true
+    }
+
+    /**
+     * Apply the latest undone action again, false if there is nothing to redo
+     */
+    pub fn redo(&mut self) -> bool {
+        let Some(action) = self.undone.pop() else {
+            return false;
+        };
+        //the shape is the same as when the action first ran, so it records the same inverse again
+        let redone = match action {
+            Action::Insert(key) => self.apply_insert(key),
+            Action::Delete { key, .. } => self.apply_delete(key),
+        };
+        self.done.extend(redone);
+        true
+    }
+
+    //an inserted key always ends up as a leaf and stays one until a later action, which is undone first
+    fn remove_leaf(&mut self, key: i32) {
+        let found = match self.root.borrow().tree_search(&key) {
+            Some(found) => found,
+            None => return,
+        };
+        let stored = BstNode::resolve_link(&self.root, &found);
+        let parent = BstNode::upgrade_weak_to_strong(stored.borrow().parent.clone());
+        match parent {
+            None => self.root.borrow_mut().key = None,
+            Some(parent) => {
+                let mut parent = parent.borrow_mut();
+                if parent.left.as_ref().is_some_and(|left| Rc::ptr_eq(left, &stored)) {
+                    parent.left = None;
+                } else {
+                    parent.right = None;
+                }
+            }
+        }
+    }
+
+    //replace whatever hangs at the deleted node's position with the subtree as it was before
+    fn restore_subtree(&mut self, parent: Option<i32>, left_side: bool, subtree: &[i32]) {
+        let rebuilt = BstNode::from_preorder(subtree).expect("recorded subtree is a valid pre-order");
+        match parent {
+            Some(parent_key) => {
+                let found = self.root.borrow().tree_search(&parent_key).unwrap();
+                let parent = BstNode::resolve_link(&self.root, &found);
+                rebuilt.borrow_mut().parent = Some(Rc::downgrade(&parent));
+                if left_side {
+                    parent.borrow_mut().left = Some(rebuilt);
+                } else {
+                    parent.borrow_mut().right = Some(rebuilt);
+                }
+            }
+            None => {
+                //the root node itself stays, only its content is put back (same as transplant)
+                let (key, left, right) = {
+                    let rebuilt = rebuilt.borrow();
+                    (rebuilt.key, rebuilt.left.clone(), rebuilt.right.clone())
+                };
+                let mut root = self.root.borrow_mut();
+                root.key = key;
+                root.left = left;
+                root.right = right;
+                for child in [&root.left, &root.right].into_iter().flatten() {
+                    child.borrow_mut().parent = Some(Rc::downgrade(&self.root));
+                }
+            }
+        }
+    }
+
+    /**
+     * Remember the current state under name, a checkpoint with the same name is moved here
+     */
+    pub fn checkpoint(&mut self, name: &str) {
+        self.checkpoints.retain(|(existing, _)| existing != name);
+        self.checkpoints.push((name.to_string(), self.done.len()));
+    }
+
+    /**
+     * Undo (or redo) until the tree is back at the checkpoint, false if there is no such checkpoint
+     */
+    pub fn restore(&mut self, name: &str) -> bool {
+        let Some(&(_, position)) = self.checkpoints.iter().find(|(existing, _)| existing == name) else {
+            return false;
+        };
+        while self.done.len() > position && self.undo() {}
+        while self.done.len() < position && self.redo() {}
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /**
+     * Oldest first: done actions, checkpoints where they were taken, then the undone actions marked as such
+     * e.g: ["insert 5", "checkpoint start", "delete 15", "undone: insert 9"]
+     */
+    pub fn history(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for position in 0..=self.done.len() {
+            for (name, _) in self.checkpoints.iter().filter(|(_, at)| *at == position) {
+                lines.push(format!("checkpoint {}", name));
+            }
+            if let Some(action) = self.done.get(position) {
+                lines.push(action.describe());
+            }
+        }
+        //undone is a stack, its top is the action right after the done ones
+        for (offset, action) in self.undone.iter().rev().enumerate() {
+            let position = self.done.len() + offset + 1;
+            lines.push(format!("undone: {}", action.describe()));
+            for (name, _) in self.checkpoints.iter().filter(|(_, at)| *at == position) {
+                lines.push(format!("checkpoint {}", name));
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BstHistory;
+    use crate::structure::bst::{BstNode, BstNodeLink};
+    use crate::test_util::{demo_bst, temp_path};
+    use crate::tool::{differential, generate_dotfile_bst};
+
+    #[test]
+    fn test_history() {
+        let rootlink = demo_bst();
+        let dot_path = temp_path("history_test.dot");
+        let dot_text = |root: &BstNodeLink| {
+            generate_dotfile_bst(root, dot_path.to_str().unwrap()).unwrap();
+            std::fs::read_to_string(&dot_path).unwrap()
+        };
+        let before = dot_text(&rootlink);
+        let mut history = BstHistory::new(&rootlink);
+        history.checkpoint("start");
+        //root with both children, an inner node, a leaf, then a fresh insert
+        assert!(history.delete(15));
+        assert!(history.delete(6));
+        assert!(history.delete(9));
+        assert!(!history.delete(100));
+        assert!(history.insert(5));
+        assert!(!history.insert(5));
+        history.checkpoint("edited");
+        let edited = dot_text(&rootlink);
+        assert_eq!(
+            history.history(),
+            vec!["checkpoint start", "delete 15", "delete 6", "delete 9", "insert 5", "checkpoint edited"]
+        );
+        assert!(history.undo());
+        assert!(history.undo());
+        assert_eq!(history.history()[3..], ["undone: delete 9", "undone: insert 5", "checkpoint edited"]);
+        assert!(history.restore("start"));
+        assert!(!history.can_undo());
+        assert!(!history.undo());
+        //the exact shape is back, not just the keys
+        assert_eq!(dot_text(&rootlink), before);
+        assert!(std::rc::Rc::ptr_eq(&history.root(), &rootlink));
+        assert!(history.restore("edited"));
+        assert_eq!(dot_text(&rootlink), edited);
+        assert!(!history.can_redo());
+        assert!(!history.restore("missing"));
+
+        //a new action drops the undone ones and the checkpoints taken among them
+        history.undo();
+        history.insert(30);
+        assert!(!history.can_redo());
+        assert!(!history.restore("edited"));
+        assert_eq!(BstNode::validate(&rootlink), Ok(()));
+
+        //random sequences from an empty tree, every undo must give back the previous pre-order exactly
+        for seed in 0..50 {
+            let empty = BstNode::new_bst_nodelink(0);
+            empty.borrow_mut().key = None;
+            let mut history = BstHistory::new(&empty);
+            let mut shapes = Vec::new();
+            for op in differential::generate(seed, 80, 20) {
+                let shape = BstNode::preorder_keys(&empty);
+                let changed = match op {
+                    differential::Op::Insert(key) => history.insert(key),
+                    differential::Op::Delete(key) => history.delete(key),
+                    _ => false,
+                };
+                if changed {
+                    shapes.push(shape);
+                }
+            }
+            while let Some(shape) = shapes.pop() {
+                assert!(history.undo());
+                assert_eq!(BstNode::preorder_keys(&empty), shape, "seed {}", seed);
+                assert_eq!(BstNode::validate(&empty), Ok(()));
+            }
+            assert!(empty.borrow().key.is_none());
+        }
+        let _ = std::fs::remove_file(&dot_path);
+    }
+}
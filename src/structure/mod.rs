@@ -1,4 +1,13 @@
+pub mod ancestry;
 pub mod bst;
+pub mod compare;
+pub mod cost;
+pub mod cursor;
+pub mod entry;
+pub mod error;
+pub mod history;
+pub mod traversal;
+pub mod validate;
 pub mod tree {
     use std::cell::RefCell;
     use std::rc::{Rc, Weak};
@@ -27,8 +36,7 @@ pub mod tree {
 
         pub fn new_nodelink(value: i32) -> NodeLink {
             let currentnode = Node::new(value);
-            let currentlink = Rc::new(RefCell::new(currentnode));
-            currentlink
+            Rc::new(RefCell::new(currentnode))
         }
 
         /**
@@ -50,13 +58,11 @@ pub mod tree {
         fn new_with_parent(parent: &NodeLink, value: i32) -> NodeLink {
             let mut currentnode = Node::new(value);
             currentnode.add_parent(Rc::<RefCell<Node>>::downgrade(parent));
-            let currentlink = Rc::new(RefCell::new(currentnode));
-            currentlink
+            Rc::new(RefCell::new(currentnode))
         }
 
         fn new_from_node(node: Node) -> NodeLink {
-            let currentlink = Rc::new(RefCell::new(node));
-            currentlink
+            Rc::new(RefCell::new(node))
         }
 
         //add new left child, set the parent to current_node_link
@@ -80,10 +86,7 @@ pub mod tree {
          * As the name implied, used to upgrade parent node to strong nodelink
          */
         pub fn upgrade_weak_to_strong(node: Option<WeakNodeLink>) -> Option<NodeLink> {
-            match node {
-                None => None,
-                Some(x) => Some(x.upgrade().unwrap()),
-            }
+            node.map(|x| x.upgrade().unwrap())
         }
 
         /**
@@ -111,7 +114,7 @@ pub mod tree {
             if node1s.is_none() && node2s.is_none() {
                 return true;
             }
-            return Node::is_node_match_both_strong(node1s, node2s);
+            Node::is_node_match_both_strong(node1s, node2s)
         }
 
         //helper function to compare both nodelink
@@ -122,7 +125,7 @@ pub mod tree {
             if let Some(node1v) = node1 {
                 return node2.is_some_and(|x: NodeLink| x.borrow().value == node1v.borrow().value);
             }
-            return false;
+            false
         }
 
         /**
@@ -144,7 +147,7 @@ pub mod tree {
             if let Some(x) = &self.right {
                 return x.borrow().get_node_by_value(value);
             }
-            return None;
+            None
         }
 
         /**
@@ -222,48 +225,43 @@ pub mod tree {
             let mut count = 0;
             let nodelink: Rc<RefCell<Node>> = Node::new_from_node(self.clone());
             count = Node::count_nodes_by_nodelink(&nodelink, count);
-            return count;
+            count
         }
 
         //the same as above except start the count from nodelink reference parameter
+        //nodes still to visit are kept on a heap stack, so a chain of any length is fine
         pub fn count_nodes_by_nodelink(node: &NodeLink, count: i32) -> i32 {
-            let mut left_count: i32 = 0;
-            let mut right_count: i32 = 0;
-            if let Some(left_child) = &node.borrow().left {
-                left_count = Node::count_nodes_by_nodelink(&left_child, count);
-            }
-            if let Some(right_child) = &node.borrow().right {
-                right_count = Node::count_nodes_by_nodelink(&right_child, count);
+            let mut count = count;
+            let mut stack: Vec<NodeLink> = vec![node.clone()];
+            while let Some(current) = stack.pop() {
+                count += 1;
+                let current = current.borrow();
+                stack.extend(current.left.clone());
+                stack.extend(current.right.clone());
             }
-            return count + left_count + right_count + 1;
+            count
         }
 
         /**Count depth of the tree in the current node
          * Count from root is started from 0
          */
         pub fn tree_depth(&self) -> i32 {
-            let depth: i32 = 0;
             let nodelink: Rc<RefCell<Node>> = Node::new_from_node(self.clone());
-            return self.track_depth(&nodelink, depth);
+            self.track_depth(&nodelink)
         }
 
         //track depth by traversing all nodes but returned depth count per path. The highest number will be returned
-        fn track_depth(&self, node: &NodeLink, depth: i32) -> i32 {
-            let mut left_depth: i32 = 0;
-            let mut right_depth: i32 = 0;
-            if let Some(left_child) = &node.borrow().left {
-                left_depth = self.track_depth(left_child, depth) + 1;
-            }
-
-            if let Some(right_child) = &node.borrow().right {
-                right_depth = self.track_depth(right_child, depth) + 1;
-            }
-
-            if left_depth > right_depth {
-                return left_depth;
+        fn track_depth(&self, node: &NodeLink) -> i32 {
+            let mut deepest: i32 = 0;
+            let mut stack: Vec<(NodeLink, i32)> = vec![(node.clone(), 0)];
+            while let Some((current, depth)) = stack.pop() {
+                deepest = deepest.max(depth);
+                let current = current.borrow();
+                for child in [&current.left, &current.right].into_iter().flatten() {
+                    stack.push((child.clone(), depth + 1));
+                }
             }
-
-            right_depth
+            deepest
         }
 
         /**
@@ -295,4 +293,22 @@ pub mod tree {
             None
         }
     }
+
+    /**
+     * Same as the BstNode drop, release the children from a heap stack instead of recursing
+     */
+    impl Drop for Node {
+        fn drop(&mut self) {
+            let mut stack: Vec<NodeLink> = Vec::new();
+            stack.extend(self.left.take());
+            stack.extend(self.right.take());
+            while let Some(node) = stack.pop() {
+                if let Ok(cell) = Rc::try_unwrap(node) {
+                    let mut inner = cell.into_inner();
+                    stack.extend(inner.left.take());
+                    stack.extend(inner.right.take());
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,375 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::{Node, NodeLink};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+/**
+ * Why a set of traversal sequences doesn't describe a tree
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraversalError {
+    //no values at all, a tree needs at least the root
+    Empty,
+    //both sequences must list every node once
+    LengthMismatch { first: usize, second: usize },
+    //with duplicates the tree can't be rebuilt uniquely
+    DuplicateValue(i32),
+    //value shows up in one sequence but not in the other
+    MissingValue(i32),
+    //value can't be placed, the sequences come from different trees
+    Inconsistent(i32),
+    //value breaks the BST ordering of a pre-order / post-order sequence
+    NotBstOrder(i32),
+}
+
+impl fmt::Display for TraversalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraversalError::Empty => write!(f, "traversal sequence is empty"),
+            TraversalError::LengthMismatch { first, second } => {
+                write!(f, "sequences have different lengths ({} and {})", first, second)
+            }
+            TraversalError::DuplicateValue(value) => write!(f, "value {} appears more than once", value),
+            TraversalError::MissingValue(value) => {
+                write!(f, "value {} is missing from one of the sequences", value)
+            }
+            TraversalError::Inconsistent(value) => {
+                write!(f, "value {} doesn't fit, sequences describe different trees", value)
+            }
+            TraversalError::NotBstOrder(value) => {
+                write!(f, "value {} breaks the binary search tree order", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraversalError {}
+
+//both sequences must be non empty, duplicate free and hold the same values
+fn check_sequences(first: &[i32], second: &[i32]) -> Result<HashMap<i32, usize>, TraversalError> {
+    if first.is_empty() || second.is_empty() {
+        return Err(TraversalError::Empty);
+    }
+    if first.len() != second.len() {
+        return Err(TraversalError::LengthMismatch {
+            first: first.len(),
+            second: second.len(),
+        });
+    }
+    let mut positions = HashMap::new();
+    for (position, &value) in second.iter().enumerate() {
+        if positions.insert(value, position).is_some() {
+            return Err(TraversalError::DuplicateValue(value));
+        }
+    }
+    let mut seen = HashSet::new();
+    for &value in first {
+        if !seen.insert(value) {
+            return Err(TraversalError::DuplicateValue(value));
+        }
+        if !positions.contains_key(&value) {
+            return Err(TraversalError::MissingValue(value));
+        }
+    }
+    Ok(positions)
+}
+
+fn check_duplicates(sequence: &[i32]) -> Result<(), TraversalError> {
+    if sequence.is_empty() {
+        return Err(TraversalError::Empty);
+    }
+    let mut seen = HashSet::new();
+    for &value in sequence {
+        if !seen.insert(value) {
+            return Err(TraversalError::DuplicateValue(value));
+        }
+    }
+    Ok(())
+}
+
+//parent link of a subtree still to be built and whether it goes on the left, None for the root
+type Slot<T> = Option<(T, bool)>;
+
+//hang child below parent on the given side, both links are set
+fn attach_node(parent: &NodeLink, child: &NodeLink, is_left: bool) {
+    child.borrow_mut().parent = Some(Rc::downgrade(parent));
+    if is_left {
+        parent.borrow_mut().left = Some(child.clone());
+    } else {
+        parent.borrow_mut().right = Some(child.clone());
+    }
+}
+
+fn attach_bst(parent: &BstNodeLink, child: &BstNodeLink, is_left: bool) {
+    child.borrow_mut().parent = Some(Rc::downgrade(parent));
+    if is_left {
+        parent.borrow_mut().left = Some(child.clone());
+    } else {
+        parent.borrow_mut().right = Some(child.clone());
+    }
+}
+
+/*
+ * The walks and builders below keep their pending work on a heap stack instead of the call stack,
+ * so a degenerate chain of any depth is handled
+ * A builder frame is a subtree still to be built: its bounds and the parent slot it goes into
+ * Frames are pushed in reverse of the order the recursive version would visit them
+ */
+impl Node {
+    pub fn preorder_values(node: &NodeLink) -> Vec<i32> {
+        let mut values = Vec::new();
+        let mut stack = vec![node.clone()];
+        while let Some(current) = stack.pop() {
+            let current = current.borrow();
+            values.push(current.value);
+            stack.extend(current.right.clone());
+            stack.extend(current.left.clone());
+        }
+        values
+    }
+
+    pub fn inorder_values(node: &NodeLink) -> Vec<i32> {
+        let mut values = Vec::new();
+        let mut stack: Vec<NodeLink> = Vec::new();
+        let mut next = Some(node.clone());
+        loop {
+            while let Some(current) = next {
+                next = current.borrow().left.clone();
+                stack.push(current);
+            }
+            let Some(current) = stack.pop() else {
+                break;
+            };
+            values.push(current.borrow().value);
+            next = current.borrow().right.clone();
+        }
+        values
+    }
+
+    //root, right, left read backwards is left, right, root
+    pub fn postorder_values(node: &NodeLink) -> Vec<i32> {
+        let mut values = Vec::new();
+        let mut stack = vec![node.clone()];
+        while let Some(current) = stack.pop() {
+            let current = current.borrow();
+            values.push(current.value);
+            stack.extend(current.left.clone());
+            stack.extend(current.right.clone());
+        }
+        values.reverse();
+        values
+    }
+
+    /**
+     * Rebuild the unique tree that has the given pre-order and in-order sequences
+     * Values have to be unique, the first pre-order value becomes the root
+     */
+    pub fn from_preorder_inorder(preorder: &[i32], inorder: &[i32]) -> Result<NodeLink, TraversalError> {
+        let positions = check_sequences(preorder, inorder)?;
+        let mut root = None;
+        //(in-order window of the subtree that starts at the next pre-order value, parent slot)
+        let mut frames: Vec<(usize, usize, Slot<NodeLink>)> = vec![(0, inorder.len(), None)];
+        let mut next = 0;
+        while let Some((in_start, in_end, slot)) = frames.pop() {
+            let value = preorder[next];
+            let position = positions[&value];
+            if position < in_start || position >= in_end {
+                return Err(TraversalError::Inconsistent(value));
+            }
+            next += 1;
+            let link = Node::new_nodelink(value);
+            match slot {
+                Some((parent, is_left)) => attach_node(&parent, &link, is_left),
+                None => root = Some(link.clone()),
+            }
+            if position + 1 < in_end {
+                frames.push((position + 1, in_end, Some((link.clone(), false))));
+            }
+            if position > in_start {
+                frames.push((in_start, position, Some((link, true))));
+            }
+        }
+        Ok(root.unwrap())
+    }
+
+    /**
+     * Rebuild the unique tree that has the given post-order and in-order sequences
+     * Values have to be unique, the last post-order value becomes the root
+     */
+    pub fn from_postorder_inorder(postorder: &[i32], inorder: &[i32]) -> Result<NodeLink, TraversalError> {
+        let positions = check_sequences(postorder, inorder)?;
+        let mut root = None;
+        //post-order read backwards is root, right, left, so the right subtree is built first
+        let mut frames: Vec<(usize, usize, Slot<NodeLink>)> = vec![(0, inorder.len(), None)];
+        let mut remaining = postorder.len();
+        while let Some((in_start, in_end, slot)) = frames.pop() {
+            let value = postorder[remaining - 1];
+            let position = positions[&value];
+            if position < in_start || position >= in_end {
+                return Err(TraversalError::Inconsistent(value));
+            }
+            remaining -= 1;
+            let link = Node::new_nodelink(value);
+            match slot {
+                Some((parent, is_left)) => attach_node(&parent, &link, is_left),
+                None => root = Some(link.clone()),
+            }
+            if position > in_start {
+                frames.push((in_start, position, Some((link.clone(), true))));
+            }
+            if position + 1 < in_end {
+                frames.push((position + 1, in_end, Some((link, false))));
+            }
+        }
+        Ok(root.unwrap())
+    }
+}
+
+impl BstNode {
+    pub fn preorder_keys(node: &BstNodeLink) -> Vec<i32> {
+        let mut keys = Vec::new();
+        let mut stack = vec![node.clone()];
+        while let Some(current) = stack.pop() {
+            let current = current.borrow();
+            keys.extend(current.key);
+            stack.extend(current.right.clone());
+            stack.extend(current.left.clone());
+        }
+        keys
+    }
+
+    pub fn postorder_keys(node: &BstNodeLink) -> Vec<i32> {
+        let mut keys = Vec::new();
+        let mut stack = vec![node.clone()];
+        while let Some(current) = stack.pop() {
+            let current = current.borrow();
+            keys.extend(current.key);
+            stack.extend(current.left.clone());
+            stack.extend(current.right.clone());
+        }
+        keys.reverse();
+        keys
+    }
+
+    /**
+     * Rebuild a BST from its pre-order sequence alone, the key order fixes the shape
+     * e.g: [15, 6, 3, 7, 18] -> 15 with 6 (3, 7) on the left and 18 on the right
+     */
+    pub fn from_preorder(preorder: &[i32]) -> Result<BstNodeLink, TraversalError> {
+        check_duplicates(preorder)?;
+        let mut root = None;
+        //(lower, upper, parent slot), a frame takes the next key only while it stays strictly between the bounds
+        let mut frames: Vec<(Option<i32>, Option<i32>, Slot<BstNodeLink>)> = vec![(None, None, None)];
+        let mut next = 0;
+        while let Some((lower, upper, slot)) = frames.pop() {
+            let Some(&key) = preorder.get(next) else {
+                break;
+            };
+            if lower.is_some_and(|lower| key <= lower) || upper.is_some_and(|upper| key >= upper) {
+                continue;
+            }
+            next += 1;
+            let link = BstNode::new_bst_nodelink(key);
+            match slot {
+                Some((parent, is_left)) => attach_bst(&parent, &link, is_left),
+                None => root = Some(link.clone()),
+            }
+            frames.push((Some(key), upper, Some((link.clone(), false))));
+            frames.push((lower, Some(key), Some((link, true))));
+        }
+        //anything left over didn't fit below the root
+        if next < preorder.len() {
+            return Err(TraversalError::NotBstOrder(preorder[next]));
+        }
+        Ok(root.unwrap())
+    }
+
+    /**
+     * Rebuild a BST from its post-order sequence alone, the last key is the root
+     */
+    pub fn from_postorder(postorder: &[i32]) -> Result<BstNodeLink, TraversalError> {
+        check_duplicates(postorder)?;
+        let mut root = None;
+        //mirror of from_preorder walking backwards, right subtree first
+        let mut frames: Vec<(Option<i32>, Option<i32>, Slot<BstNodeLink>)> = vec![(None, None, None)];
+        let mut remaining = postorder.len();
+        while let Some((lower, upper, slot)) = frames.pop() {
+            let Some(&key) = remaining.checked_sub(1).and_then(|last| postorder.get(last)) else {
+                break;
+            };
+            if lower.is_some_and(|lower| key <= lower) || upper.is_some_and(|upper| key >= upper) {
+                continue;
+            }
+            remaining -= 1;
+            let link = BstNode::new_bst_nodelink(key);
+            match slot {
+                Some((parent, is_left)) => attach_bst(&parent, &link, is_left),
+                None => root = Some(link.clone()),
+            }
+            frames.push((lower, Some(key), Some((link.clone(), true))));
+            frames.push((Some(key), upper, Some((link, false))));
+        }
+        if remaining > 0 {
+            return Err(TraversalError::NotBstOrder(postorder[remaining - 1]));
+        }
+        Ok(root.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TraversalError;
+    use crate::structure::bst::BstNode;
+    use crate::structure::tree::Node;
+    use crate::test_util::demo_bst;
+    use crate::tool::{serialize, tree_text};
+
+    #[test]
+    fn test_traversal_rebuild() {
+        //88 (48, 888 (84, -)) from prime_t4, a lone left child
+        let treelink = tree_text::read_treefile(concat!(env!("CARGO_MANIFEST_DIR"), "/prime_t4")).unwrap();
+        let preorder = Node::preorder_values(&treelink);
+        let inorder = Node::inorder_values(&treelink);
+        let postorder = Node::postorder_values(&treelink);
+        assert_eq!(preorder, vec![88, 48, 888, 84]);
+        assert_eq!(inorder, vec![48, 88, 84, 888]);
+        assert_eq!(postorder, vec![48, 84, 888, 88]);
+        let expected = serialize::tree_to_json(&treelink);
+        let rebuilt = Node::from_preorder_inorder(&preorder, &inorder).unwrap();
+        assert_eq!(serialize::tree_to_json(&rebuilt), expected);
+        let rebuilt = Node::from_postorder_inorder(&postorder, &inorder).unwrap();
+        assert_eq!(serialize::tree_to_json(&rebuilt), expected);
+        let eighty_four = rebuilt.borrow().right.clone().unwrap().borrow().left.clone().unwrap();
+        let eighty_four_parent = Node::upgrade_weak_to_strong(eighty_four.borrow().parent.clone()).unwrap();
+        assert_eq!(eighty_four_parent.borrow().value, 888);
+
+        assert_eq!(Node::from_preorder_inorder(&[], &[]).unwrap_err(), TraversalError::Empty);
+        assert_eq!(
+            Node::from_preorder_inorder(&[1, 2], &[1]).unwrap_err(),
+            TraversalError::LengthMismatch { first: 2, second: 1 }
+        );
+        assert_eq!(Node::from_preorder_inorder(&[1, 1], &[1, 1]).unwrap_err(), TraversalError::DuplicateValue(1));
+        assert_eq!(Node::from_preorder_inorder(&[1, 3], &[1, 2]).unwrap_err(), TraversalError::MissingValue(3));
+        assert_eq!(
+            Node::from_preorder_inorder(&[1, 2, 3], &[3, 1, 2]).unwrap_err(),
+            TraversalError::Inconsistent(2)
+        );
+
+        let rootlink = demo_bst();
+        let expected = serialize::bst_to_json(&rootlink);
+        let rebuilt = BstNode::from_preorder(&BstNode::preorder_keys(&rootlink)).unwrap();
+        assert_eq!(serialize::bst_to_json(&rebuilt), expected);
+        let rebuilt = BstNode::from_postorder(&BstNode::postorder_keys(&rootlink)).unwrap();
+        assert_eq!(serialize::bst_to_json(&rebuilt), expected);
+        let nine = rebuilt.borrow().tree_search(&9).unwrap();
+        let nine_parent = BstNode::upgrade_weak_to_strong(nine.borrow().parent.clone()).unwrap();
+        assert_eq!(nine_parent.borrow().key, Some(13));
+
+        //3 would have to sit on the left of 5 after 7 already went right
+        assert_eq!(BstNode::from_preorder(&[5, 7, 3]).unwrap_err(), TraversalError::NotBstOrder(3));
+        assert_eq!(BstNode::from_postorder(&[7, 3, 5]).unwrap_err(), TraversalError::NotBstOrder(7));
+        assert_eq!(BstNode::from_preorder(&[5, 3, 5]).unwrap_err(), TraversalError::DuplicateValue(5));
+        assert_eq!(BstNode::from_postorder(&[]).unwrap_err(), TraversalError::Empty);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use std::fmt;
+use std::rc::Rc;
+
+/**
+ * A broken invariant found by BstNode::validate, the key is the node where it was noticed
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvariantError {
+    //the root still points to a parent
+    RootHasParent(i32),
+    //a node below the root (or a root with children) has key None
+    MissingKey,
+    //key is outside the range its ancestors allow, this also catches duplicates
+    OutOfOrder { key: i32, low: Option<i32>, high: Option<i32> },
+    //the parent pointer of a child doesn't lead back to the node holding it
+    WrongParent(i32),
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::RootHasParent(key) => write!(f, "root {} has a parent", key),
+            InvariantError::MissingKey => write!(f, "a node that should hold a key has none"),
+            InvariantError::OutOfOrder { key, low, high } => {
+                let bound = |value: &Option<i32>| value.map_or("-inf/+inf".to_string(), |v| v.to_string());
+                write!(f, "key {} is outside ({}, {})", key, bound(low), bound(high))
+            }
+            InvariantError::WrongParent(key) => write!(f, "parent pointer of {} is wrong", key),
+        }
+    }
+}
+
+impl std::error::Error for InvariantError {}
+
+impl BstNode {
+    /**
+     * Check the whole tree below root:
+     * 1). every key lies strictly between the bounds set by its ancestors (order, no duplicates),
+     * 2). every child's parent pointer is the very node holding it,
+     * 3). only an empty tree (a lone root) may have key None, and the root has no parent
+     * Iterative, so degenerate trees of any depth are fine
+     */
+    pub fn validate(root: &BstNodeLink) -> Result<(), InvariantError> {
+        {
+            let root_ref = root.borrow();
+            match root_ref.key {
+                None if root_ref.left.is_none() && root_ref.right.is_none() => return Ok(()),
+                None => return Err(InvariantError::MissingKey),
+                Some(key) if BstNode::upgrade_weak_to_strong(root_ref.parent.clone()).is_some() => {
+                    return Err(InvariantError::RootHasParent(key))
+                }
+                Some(_) => {}
+            }
+        }
+        let mut stack: Vec<(BstNodeLink, Option<i32>, Option<i32>)> = vec![(root.clone(), None, None)];
+        while let Some((node, low, high)) = stack.pop() {
+            let node_ref = node.borrow();
+            let key = node_ref.key.ok_or(InvariantError::MissingKey)?;
+            if low.is_some_and(|low| key <= low) || high.is_some_and(|high| key >= high) {
+                return Err(InvariantError::OutOfOrder { key, low, high });
+            }
+            for (child, child_low, child_high) in [(&node_ref.left, low, Some(key)), (&node_ref.right, Some(key), high)] {
+                if let Some(child) = child {
+                    let parent = child.borrow().parent.as_ref().and_then(|parent| parent.upgrade());
+                    if !parent.is_some_and(|parent| Rc::ptr_eq(&parent, &node)) {
+                        return Err(InvariantError::WrongParent(child.borrow().key.unwrap_or(key)));
+                    }
+                    stack.push((child.clone(), child_low, child_high));
+                }
+            }
+        }
+        Ok(())
+    }
+}
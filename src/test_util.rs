@@ -0,0 +1,46 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/**
+ * Shared fixtures of the unit tests
+ * demo_bst is the tree of main: 15 then 6, 18, 17, 20, 3, 7, 2, 4, 13, 9 inserted in that order, i.e.
+ *            15
+ *        6        18
+ *      3   7    17  20
+ *     2 4   13
+ *          9
+ */
+pub(crate) fn demo_bst() -> BstNodeLink {
+    let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
+    for key in [6, 18, 17, 20, 3, 7, 2, 4, 13, 9] {
+        BstNode::tree_insert(&rootlink, key);
+    }
+    rootlink
+}
+
+/**
+ * Keys 0..length as a right spine, what sorted inserts give
+ * Every key hangs right below the previous one, inserting from the root would walk the spine every time
+ */
+pub(crate) fn chain(length: i32) -> BstNodeLink {
+    let rootlink: BstNodeLink = BstNode::new_bst_nodelink(0);
+    let mut tail = rootlink.clone();
+    for key in 1..length {
+        tail.borrow_mut().add_right_child(&tail, key);
+        let next = tail.borrow().right.clone().unwrap();
+        tail = next;
+    }
+    rootlink
+}
+
+static NEXT_PATH: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * A path in the temp dir no other test uses, also across test binaries running at the same time
+ * e.g: temp_path("graph.dot") is /tmp/daabst_4242_3_graph.dot
+ */
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    let number = NEXT_PATH.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("daabst_{}_{}_{}", std::process::id(), number, name))
+}
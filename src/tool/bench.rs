@@ -0,0 +1,288 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::cost::CostLog;
+use crate::tool::generate_dotfile_bst;
+use crate::tool::rng::Rng;
+use std::collections::BTreeSet;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/**
+ * Benchmark harness, run with: cargo run --release -- bench [size ...]
+ * Every workload is a sequence of keys, it is inserted, searched, walked in order and deleted again,
+ * the same sequence is fed to std::collections::BTreeSet for comparison
+ * tree::Node has no ordered insert or search, so BstNode is the only variant measured
+ * Sorted and reverse keys degenerate the BST into a chain, keep those sizes moderate
+ */
+pub const DEFAULT_SIZES: [usize; 2] = [1_000, 10_000];
+
+//fixed so two runs measure the same key sequences
+const SEED: u64 = 0x5eed_0b57;
+//exponent of the zipf distribution, 1.0 is the classic "few keys are hot" shape
+const ZIPF_EXPONENT: f64 = 1.0;
+
+#[derive(Clone, Copy)]
+pub enum Workload {
+    Sorted,
+    Random,
+    Reverse,
+    Zipf,
+}
+
+impl Workload {
+    pub const ALL: [Workload; 4] = [Workload::Sorted, Workload::Random, Workload::Reverse, Workload::Zipf];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Workload::Sorted => "sorted",
+            Workload::Random => "random",
+            Workload::Reverse => "reverse",
+            Workload::Zipf => "zipf",
+        }
+    }
+
+    /**
+     * size keys, distinct for every workload except zipf where popular keys repeat
+     */
+    pub fn keys(&self, size: usize, rng: &mut Rng) -> Vec<i32> {
+        let size = size as i32;
+        match self {
+            Workload::Sorted => (0..size).collect(),
+            Workload::Reverse => (0..size).rev().collect(),
+            Workload::Random => {
+                let mut keys: Vec<i32> = (0..size).collect();
+                rng.shuffle(&mut keys);
+                keys
+            }
+            Workload::Zipf => {
+                //key k (0 based) has weight 1 / (k + 1)^s, draw by inverting the cumulative weights
+                let mut cumulative = Vec::with_capacity(size as usize);
+                let mut total = 0.0;
+                for k in 0..size {
+                    total += 1.0 / ((k + 1) as f64).powf(ZIPF_EXPONENT);
+                    cumulative.push(total);
+                }
+                //spread the ranks over the key space so hot keys aren't all on the left spine
+                let mut rank_to_key: Vec<i32> = (0..size).collect();
+                rng.shuffle(&mut rank_to_key);
+                (0..size)
+                    .map(|_| {
+                        let target = rng.next_f64() * total;
+                        let rank = cumulative.partition_point(|&weight| weight < target);
+                        rank_to_key[rank.min(size as usize - 1)]
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/**
+ * One measured operation, throughput is ops per second
+ */
+pub struct Sample {
+    pub operation: &'static str,
+    pub ops: usize,
+    pub bst: Duration,
+    //None when BTreeSet has nothing comparable (dot export)
+    pub btree: Option<Duration>,
+}
+
+impl Sample {
+    fn throughput(ops: usize, elapsed: Duration) -> f64 {
+        ops as f64 / elapsed.as_secs_f64().max(1e-9)
+    }
+
+    pub fn bst_throughput(&self) -> f64 {
+        Sample::throughput(self.ops, self.bst)
+    }
+
+    pub fn btree_throughput(&self) -> Option<f64> {
+        self.btree.map(|elapsed| Sample::throughput(self.ops, elapsed))
+    }
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> Duration {
+    let start = Instant::now();
+    black_box(f());
+    start.elapsed()
+}
+
+/**
+ * Run every operation of one workload on both trees, no keys give no samples
+ */
+pub fn measure(keys: &[i32]) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    let ops = keys.len();
+    if keys.is_empty() {
+        return samples;
+    }
+
+    //both start empty and get every key, an empty root (key None) takes the first one in tree_insert
+    let mut rootlink: BstNodeLink = BstNode::new_bst_nodelink(0);
+    rootlink.borrow_mut().key = None;
+    let bst = time(|| {
+        for &key in keys {
+            BstNode::tree_insert(&rootlink, key);
+        }
+    });
+    let mut set = BTreeSet::new();
+    let btree = time(|| {
+        for &key in keys {
+            set.insert(key);
+        }
+    });
+    samples.push(Sample { operation: "insert", ops, bst, btree: Some(btree) });
+
+    let bst = time(|| {
+        let root = rootlink.borrow();
+        keys.iter().filter(|&key| root.tree_search(key).is_some()).count()
+    });
+    let btree = time(|| keys.iter().filter(|&key| set.contains(key)).count());
+    samples.push(Sample { operation: "search", ops, bst, btree: Some(btree) });
+
+    let bst = time(|| {
+        let mut walked = 0;
+        let mut node = Some(rootlink.borrow().minimum());
+        while let Some(current) = node {
+            walked += 1;
+            node = BstNode::tree_successor(&current);
+        }
+        walked
+    });
+    let btree = time(|| set.iter().map(|&key| key as i64).sum::<i64>());
+    samples.push(Sample { operation: "successor walk", ops: set.len(), bst, btree: Some(btree) });
+
+    //named after the process so benchmarks running side by side don't share the file
+    let dot_path = std::env::temp_dir().join(format!("daabst_bench_{}.dot", std::process::id()));
+    let bst = time(|| generate_dotfile_bst(&rootlink, dot_path.to_str().unwrap()).expect("Failed to write the dot file"));
+    let _ = std::fs::remove_file(&dot_path);
+    samples.push(Sample { operation: "dot export", ops: set.len(), bst, btree: None });
+
+    let bst = time(|| {
+        for key in keys {
+            let found = rootlink.borrow().tree_search(key);
+            if let Some(node) = found {
+                BstNode::tree_delete(&mut rootlink, &node);
+            }
+        }
+    });
+    let btree = time(|| {
+        for key in keys {
+            set.remove(key);
+        }
+    });
+    samples.push(Sample { operation: "delete", ops, bst, btree: Some(btree) });
+    samples
+}
+
+/**
+ * Print one table row per workload, size and operation
+ * ratio is BTreeSet throughput divided by BstNode throughput, above 1 means BTreeSet is faster
+ */
+pub fn run_benchmarks(sizes: &[usize]) {
+    if cfg!(debug_assertions) {
+        println!("note: debug build, run with --release for meaningful numbers");
+    }
+    println!(
+        "{:<8} {:>8} {:<15} {:>14} {:>14} {:>8}",
+        "workload", "size", "operation", "bst ops/s", "btree ops/s", "ratio"
+    );
+    let mut rng = Rng::new(SEED);
+    for workload in Workload::ALL {
+        for &size in sizes.iter().filter(|&&size| size > 0) {
+            let keys = workload.keys(size, &mut rng);
+            for sample in measure(&keys) {
+                let bst = sample.bst_throughput();
+                let (btree, ratio) = match sample.btree_throughput() {
+                    Some(btree) => (format!("{:.0}", btree), format!("{:.2}", btree / bst)),
+                    None => ("-".to_string(), "-".to_string()),
+                };
+                println!(
+                    "{:<8} {:>8} {:<15} {:>14.0} {:>14} {:>8}",
+                    workload.name(),
+                    size,
+                    sample.operation,
+                    bst,
+                    btree,
+                    ratio
+                );
+            }
+        }
+    }
+}
+
+/**
+ * Same workload as measure, but with the cost counters instead of a clock: one record per
+ * tree_insert, tree_search, tree_successor and tree_delete call, each with the tree height at that time
+ * No keys give an empty log
+ */
+pub fn collect_costs(keys: &[i32]) -> CostLog {
+    let mut log = CostLog::new();
+    if keys.is_empty() {
+        return log;
+    }
+    let mut rootlink: BstNodeLink = BstNode::new_bst_nodelink(keys[0]);
+    for &key in &keys[1..] {
+        log.measure("tree_insert", key, &rootlink, |costs| BstNode::tree_insert_counted(&rootlink, key, costs));
+    }
+    for &key in keys {
+        let found = log.measure("tree_search", key, &rootlink, |costs| rootlink.borrow().tree_search_counted(&key, costs));
+        if let Some(node) = found {
+            log.measure("tree_successor", key, &rootlink, |costs| BstNode::tree_successor_counted(&node, costs));
+        }
+    }
+    for &key in keys {
+        let found = rootlink.borrow().tree_search(&key);
+        if let Some(node) = found {
+            let root = rootlink.clone();
+            log.measure("tree_delete", key, &root, |costs| BstNode::tree_delete_counted(&mut rootlink, &node, costs));
+        }
+    }
+    log
+}
+
+/**
+ * Write one costs_<workload>.csv per workload into dir, for plotting the costs against the height
+ */
+pub fn run_costs(size: usize, dir: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut rng = Rng::new(SEED);
+    for workload in Workload::ALL {
+        let keys = workload.keys(size.max(1), &mut rng);
+        let path = std::path::Path::new(dir).join(format!("costs_{}.csv", workload.name()));
+        collect_costs(&keys).write_csv(path.to_str().unwrap())?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench() {
+        let mut rng = crate::tool::rng::Rng::new(7);
+        for workload in Workload::ALL {
+            let keys = workload.keys(500, &mut rng);
+            assert_eq!(keys.len(), 500);
+            assert!(keys.iter().all(|&key| (0..500).contains(&key)));
+            if !matches!(workload, Workload::Zipf) {
+                let mut sorted = keys.clone();
+                sorted.sort();
+                assert_eq!(sorted, (0..500).collect::<Vec<i32>>());
+            }
+            let samples = measure(&keys);
+            let operations: Vec<&str> = samples.iter().map(|sample| sample.operation).collect();
+            assert_eq!(operations, vec!["insert", "search", "successor walk", "dot export", "delete"]);
+            assert!(samples.iter().all(|sample| sample.bst_throughput() > 0.0));
+        }
+        //nothing to measure is not an error
+        assert!(measure(&[]).is_empty());
+        assert!(collect_costs(&[]).records().is_empty());
+        //the same seed repeats the same workload
+        let first = Workload::Zipf.keys(100, &mut crate::tool::rng::Rng::new(3));
+        let second = Workload::Zipf.keys(100, &mut crate::tool::rng::Rng::new(3));
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::{Node, NodeLink};
+
+/**
+ * Flat copy of a tree for the drawing exporters (svg, pretty, diagram)
+ * Node labels with child indices into the same Vec, the root is entry 0 and the order is pre-order,
+ * so a parent always comes before its children
+ */
+pub(crate) struct Cell {
+    pub label: String,
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+}
+
+//hang the cell at index below parent on the given side
+fn attach(cells: &mut [Cell], parent: Option<usize>, is_left: bool, index: usize) {
+    if let Some(parent) = parent {
+        if is_left {
+            cells[parent].left = Some(index);
+        } else {
+            cells[parent].right = Some(index);
+        }
+    }
+}
+
+/**
+ * An empty root (key None) gives no cells, a child without key is labeled "?"
+ */
+pub(crate) fn cells_of_bst(root: &BstNode) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    let Some(key) = root.key else {
+        return cells;
+    };
+    cells.push(Cell { label: key.to_string(), left: None, right: None });
+    //(node, index of its parent cell, is left child), right pushed before left so the left one comes first
+    let mut stack: Vec<(BstNodeLink, Option<usize>, bool)> = Vec::new();
+    stack.extend(root.right.clone().map(|right| (right, Some(0), false)));
+    stack.extend(root.left.clone().map(|left| (left, Some(0), true)));
+    while let Some((node, parent, is_left)) = stack.pop() {
+        let node = node.borrow();
+        let index = cells.len();
+        cells.push(Cell { label: node.key.map_or("?".to_string(), |key| key.to_string()), left: None, right: None });
+        attach(&mut cells, parent, is_left, index);
+        stack.extend(node.right.clone().map(|right| (right, Some(index), false)));
+        stack.extend(node.left.clone().map(|left| (left, Some(index), true)));
+    }
+    cells
+}
+
+pub(crate) fn cells_of_tree(root: &Node) -> Vec<Cell> {
+    let mut cells = vec![Cell { label: root.value.to_string(), left: None, right: None }];
+    let mut stack: Vec<(NodeLink, Option<usize>, bool)> = Vec::new();
+    stack.extend(root.right.clone().map(|right| (right, Some(0), false)));
+    stack.extend(root.left.clone().map(|left| (left, Some(0), true)));
+    while let Some((node, parent, is_left)) = stack.pop() {
+        let node = node.borrow();
+        let index = cells.len();
+        cells.push(Cell { label: node.value.to_string(), left: None, right: None });
+        attach(&mut cells, parent, is_left, index);
+        stack.extend(node.right.clone().map(|right| (right, Some(index), false)));
+        stack.extend(node.left.clone().map(|left| (left, Some(index), true)));
+    }
+    cells
+}
+
+/**
+ * Index of the parent of every cell, None for the root
+ */
+pub(crate) fn parents(cells: &[Cell]) -> Vec<Option<usize>> {
+    let mut parent_of = vec![None; cells.len()];
+    for (index, cell) in cells.iter().enumerate() {
+        for child in [cell.left, cell.right].into_iter().flatten() {
+            parent_of[child] = Some(index);
+        }
+    }
+    parent_of
+}
@@ -0,0 +1,204 @@
+use crate::structure::bst::BstNodeLink;
+use crate::structure::tree::NodeLink;
+use crate::tool::cells::{cells_of_bst, cells_of_tree, Cell};
+use std::fs;
+use std::io;
+
+/**
+ * Text diagrams to embed in documents, no Graphviz step needed
+ * Mermaid (graph TD), paste into a ```mermaid block of a Markdown file:
+ *   graph TD
+ *       n0["15"]
+ *       n0 --> n1
+ * LaTeX forest, needs \usepackage{forest}:
+ *   \begin{forest}
+ *   for tree={circle, draw}
+ *   [15
+ *     [6]
+ *     [,phantom]
+ *   ]
+ *   \end{forest}
+ * Children keep their side, a lone child gets an invisible sibling (~~~ link / phantom node) on the other side
+ */
+const INDENT: &str = "    ";
+
+//labels always go inside quotes, a quote in the label would end them early so it becomes an entity
+fn mermaid_label(label: &str) -> String {
+    format!("\"{}\"", label.replace('"', "#quot;"))
+}
+
+fn mermaid(cells: &[Cell]) -> String {
+    let mut text = "graph TD\n".to_string();
+    if cells.is_empty() {
+        text += &format!("{}empty[\"(empty)\"]\n", INDENT);
+        return text;
+    }
+    for (index, cell) in cells.iter().enumerate() {
+        text += &format!("{}n{}[{}]\n", INDENT, index, mermaid_label(&cell.label));
+    }
+    let mut placeholders = false;
+    for (index, cell) in cells.iter().enumerate() {
+        if cell.left.is_none() && cell.right.is_none() {
+            continue;
+        }
+        for (child, side) in [(cell.left, "L"), (cell.right, "R")] {
+            match child {
+                Some(child) => text += &format!("{}n{} --> n{}\n", INDENT, index, child),
+                None => {
+                    placeholders = true;
+                    text += &format!("{}n{}_{}[ ]:::nil\n", INDENT, index, side);
+                    text += &format!("{}n{} ~~~ n{}_{}\n", INDENT, index, index, side);
+                }
+            }
+        }
+    }
+    if placeholders {
+        text += &format!("{}classDef nil fill:none,stroke:none\n", INDENT);
+    }
+    text
+}
+
+//math mode so a negative key gets a real minus sign
+fn forest_label(label: &str) -> String {
+    if label.starts_with('-') {
+        format!("${}$", label)
+    } else {
+        label.to_string()
+    }
+}
+
+//pending work of the forest writer, each with its depth
+enum Item {
+    Open(usize),
+    Phantom,
+    Close,
+}
+
+fn forest(cells: &[Cell]) -> String {
+    let mut text = "\\begin{forest}\nfor tree={circle, draw}\n".to_string();
+    if cells.is_empty() {
+        text += "[(empty), draw=none]\n";
+    }
+    let mut stack: Vec<(Item, usize)> = if cells.is_empty() { Vec::new() } else { vec![(Item::Open(0), 0)] };
+    while let Some((item, depth)) = stack.pop() {
+        let indent = "  ".repeat(depth);
+        match item {
+            Item::Open(index) => {
+                let cell = &cells[index];
+                let label = forest_label(&cell.label);
+                if cell.left.is_none() && cell.right.is_none() {
+                    text += &format!("{}[{}]\n", indent, label);
+                    continue;
+                }
+                text += &format!("{}[{}\n", indent, label);
+                //right pushed before left so the left one is written first
+                stack.push((Item::Close, depth));
+                for child in [cell.right, cell.left] {
+                    stack.push((child.map_or(Item::Phantom, Item::Open), depth + 1));
+                }
+            }
+            Item::Phantom => text += &format!("{}[,phantom]\n", indent),
+            Item::Close => text += &format!("{}]\n", indent),
+        }
+    }
+    text + "\\end{forest}\n"
+}
+
+pub fn bst_to_mermaid(root: &BstNodeLink) -> String {
+    mermaid(&cells_of_bst(&root.borrow()))
+}
+
+pub fn tree_to_mermaid(root: &NodeLink) -> String {
+    mermaid(&cells_of_tree(&root.borrow()))
+}
+
+pub fn bst_to_forest(root: &BstNodeLink) -> String {
+    forest(&cells_of_bst(&root.borrow()))
+}
+
+pub fn tree_to_forest(root: &NodeLink) -> String {
+    forest(&cells_of_tree(&root.borrow()))
+}
+
+/**
+ * Same as generate_dotfile_bst but writes the Mermaid diagram, e.g: tree.mmd
+ */
+pub fn generate_mermaidfile_bst(root: &BstNodeLink, output_path: &str) -> io::Result<()> {
+    fs::write(output_path, bst_to_mermaid(root))
+}
+
+pub fn generate_mermaidfile(root: &NodeLink, output_path: &str) -> io::Result<()> {
+    fs::write(output_path, tree_to_mermaid(root))
+}
+
+/**
+ * Same as generate_dotfile_bst but writes the forest environment, ready for \input{tree.tex}
+ */
+pub fn generate_forestfile_bst(root: &BstNodeLink, output_path: &str) -> io::Result<()> {
+    fs::write(output_path, bst_to_forest(root))
+}
+
+pub fn generate_forestfile(root: &NodeLink, output_path: &str) -> io::Result<()> {
+    fs::write(output_path, tree_to_forest(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::{BstNode, BstNodeLink};
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn test_diagram() {
+        //node names follow pre-order, left before right
+        let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
+        for key in [6, 18, 7, -2] {
+            BstNode::tree_insert(&rootlink, key);
+        }
+        let mermaid = bst_to_mermaid(&rootlink);
+        assert_eq!(
+            mermaid,
+            concat!(
+                "graph TD\n    n0[\"15\"]\n    n1[\"6\"]\n    n2[\"-2\"]\n    n3[\"7\"]\n    n4[\"18\"]\n",
+                "    n0 --> n1\n    n0 --> n4\n    n1 --> n2\n    n1 --> n3\n"
+            )
+        );
+        let forest = bst_to_forest(&rootlink);
+        assert_eq!(
+            forest,
+            "\\begin{forest}\nfor tree={circle, draw}\n[15\n  [6\n    [$-2$]\n    [7]\n  ]\n  [18]\n]\n\\end{forest}\n"
+        );
+
+        //a lone child keeps its side through an invisible sibling
+        let treelink: NodeLink = Node::new_nodelink(1);
+        treelink.borrow_mut().add_right_child(&treelink, 2);
+        let right = treelink.borrow().right.clone().unwrap();
+        right.borrow_mut().add_left_child(&right, 2);
+        let mermaid = tree_to_mermaid(&treelink);
+        assert!(mermaid.contains("    n0_L[ ]:::nil\n    n0 ~~~ n0_L\n    n0 --> n1\n"));
+        assert!(mermaid.contains("    n1 --> n2\n    n1_R[ ]:::nil\n    n1 ~~~ n1_R\n"));
+        assert!(mermaid.ends_with("    classDef nil fill:none,stroke:none\n"));
+        let forest = tree_to_forest(&treelink);
+        assert!(forest.contains("[1\n  [,phantom]\n  [2\n    [2]\n    [,phantom]\n  ]\n]\n"));
+
+        let empty: BstNodeLink = BstNode::new_bst_nodelink(1);
+        let one = empty.borrow().tree_search(&1).unwrap();
+        BstNode::tree_delete(&mut empty.clone(), &BstNode::resolve_link(&empty, &one));
+        assert_eq!(bst_to_mermaid(&empty), "graph TD\n    empty[\"(empty)\"]\n");
+        assert!(bst_to_forest(&empty).contains("\n[(empty), draw=none]\n"));
+
+        let output_path = temp_path("diagram_test.mmd");
+        let path = output_path.to_str().unwrap();
+        generate_mermaidfile_bst(&rootlink, path).unwrap();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), bst_to_mermaid(&rootlink));
+        generate_mermaidfile(&treelink, path).unwrap();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), tree_to_mermaid(&treelink));
+        generate_forestfile_bst(&rootlink, path).unwrap();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), bst_to_forest(&rootlink));
+        generate_forestfile(&treelink, path).unwrap();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), tree_to_forest(&treelink));
+        let _ = std::fs::remove_file(&output_path);
+        assert!(generate_mermaidfile_bst(&rootlink, "/nonexistent_dir/tree.mmd").is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::tool::rng::Rng;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/**
+ * Randomized differential testing, run with: cargo run -- fuzz [first seed] [runs] [length]
+ * The same operation sequence goes to a BstNode tree and to a BTreeSet, after every step the answers
+ * are compared, the in-order keys must match the set and BstNode::validate must pass
+ * A failing sequence is shrunk to a minimal reproducer before it is reported
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Insert(i32),
+    Delete(i32),
+    Search(i32),
+    Successor(i32),
+}
+
+/**
+ * First step that went wrong, a panic inside the tree counts as a failure too
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub step: usize,
+    pub op: Op,
+    pub message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {} ({:?}): {}", self.step, self.op, self.message)
+    }
+}
+
+/**
+ * length operations on keys in 0..key_space, a small key space makes hits and duplicates common
+ */
+pub fn generate(seed: u64, length: usize, key_space: i32) -> Vec<Op> {
+    let mut rng = Rng::new(seed);
+    (0..length)
+        .map(|_| {
+            let key = rng.below(key_space.max(1) as u64) as i32;
+            match rng.below(100) {
+                0..=39 => Op::Insert(key),
+                40..=69 => Op::Delete(key),
+                70..=84 => Op::Search(key),
+                _ => Op::Successor(key),
+            }
+        })
+        .collect()
+}
+
+//one step on both sides, Err describes the first difference
+fn apply(root: &mut BstNodeLink, model: &mut BTreeSet<i32>, op: Op) -> Result<(), String> {
+    match op {
+        Op::Insert(key) => {
+            BstNode::tree_insert(root, key);
+            model.insert(key);
+        }
+        Op::Delete(key) => {
+            let found = root.borrow().tree_search(&key);
+            if found.is_some() != model.contains(&key) {
+                return Err(format!("search before delete found {}, set has {}", found.is_some(), model.contains(&key)));
+            }
+            if let Some(node) = found {
+                BstNode::tree_delete(root, &node);
+                model.remove(&key);
+            }
+        }
+        Op::Search(key) => {
+            let found = root.borrow().tree_search(&key).and_then(|node| node.borrow().key);
+            let expected = model.get(&key).copied();
+            if found != expected {
+                return Err(format!("search returned {:?}, expected {:?}", found, expected));
+            }
+        }
+        Op::Successor(key) => {
+            let Some(node) = root.borrow().tree_search(&key) else {
+                return Ok(());
+            };
+            let found = BstNode::tree_successor(&node).and_then(|node| node.borrow().key);
+            let expected = model.range(key + 1..).next().copied();
+            if found != expected {
+                return Err(format!("successor returned {:?}, expected {:?}", found, expected));
+            }
+        }
+    }
+    if let Err(error) = BstNode::validate(root) {
+        return Err(format!("invalid tree: {}", error));
+    }
+    let keys = BstNode::inorder_keys(root);
+    if !keys.iter().eq(model.iter()) {
+        return Err(format!("tree holds {:?}, set holds {:?}", keys, model));
+    }
+    Ok(())
+}
+
+/**
+ * Replay ops on an empty tree (a root with key None) and on an empty BTreeSet
+ */
+pub fn run(ops: &[Op]) -> Result<(), Failure> {
+    let mut root = BstNode::new_bst_nodelink(0);
+    root.borrow_mut().key = None;
+    let mut model = BTreeSet::new();
+    //the panic hook is global to the process, so it is left alone, a caught panic still gets printed
+    let mut result = Ok(());
+    for (step, &op) in ops.iter().enumerate() {
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| apply(&mut root, &mut model, op)));
+        let message = match outcome {
+            Ok(Ok(())) => continue,
+            Ok(Err(message)) => message,
+            Err(payload) => {
+                let text = payload
+                    .downcast_ref::<&str>()
+                    .map(|text| text.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                format!("panicked: {}", text)
+            }
+        };
+        result = Err(Failure { step, op, message });
+        break;
+    }
+    result
+}
+
+/**
+ * Shrink a sequence for which fails(ops) holds, the result still fails
+ * but removing any single operation from it makes fails false (1-minimal)
+ * Chunks are removed first, halving the chunk size until single operations are tried
+ */
+pub fn shrink<F: FnMut(&[Op]) -> bool>(ops: Vec<Op>, mut fails: F) -> Vec<Op> {
+    let mut ops = ops;
+    let mut chunk = ops.len().div_ceil(2).max(1);
+    loop {
+        let mut start = 0;
+        let mut removed_any = false;
+        while start < ops.len() {
+            let end = (start + chunk).min(ops.len());
+            let candidate: Vec<Op> = ops[..start].iter().chain(&ops[end..]).copied().collect();
+            if fails(&candidate) {
+                ops = candidate;
+                removed_any = true;
+            } else {
+                start = end;
+            }
+        }
+        if chunk == 1 && !removed_any {
+            return ops;
+        }
+        if !removed_any {
+            chunk = chunk.div_ceil(2);
+        }
+    }
+}
+
+/**
+ * Run one seed, on failure return the shrunk reproducer with the failure it triggers
+ */
+pub fn check_seed(seed: u64, length: usize, key_space: i32) -> Result<(), (Vec<Op>, Failure)> {
+    let ops = generate(seed, length, key_space);
+    let Err(failure) = run(&ops) else {
+        return Ok(());
+    };
+    //everything after the failing step is irrelevant
+    let ops = ops[..=failure.step].to_vec();
+    let minimal = shrink(ops, |candidate| run(candidate).is_err());
+    let failure = run(&minimal).unwrap_err();
+    Err((minimal, failure))
+}
+
+/**
+ * Run runs seeds starting at first_seed, report the first failure and stop
+ */
+pub fn run_fuzz(first_seed: u64, runs: u64, length: usize) -> bool {
+    //roughly one key per two operations keeps the tree filled without making every insert a duplicate
+    let key_space = (length / 2).max(4) as i32;
+    for seed in first_seed..first_seed + runs {
+        if let Err((ops, failure)) = check_seed(seed, length, key_space) {
+            println!("seed {} failed at {}", seed, failure);
+            println!("minimal reproducer ({} ops): {:?}", ops.len(), ops);
+            return false;
+        }
+    }
+    println!("{} runs of {} operations passed (seeds {}..{})", runs, length, first_seed, first_seed + runs);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::BstNode;
+    use crate::structure::validate::InvariantError;
+    use crate::test_util::demo_bst;
+
+    #[test]
+    fn test_differential() {
+        //the validator accepts a good tree and an empty root, and spots broken ones
+        let rootlink = demo_bst();
+        assert_eq!(BstNode::validate(&rootlink), Ok(()));
+        let empty = BstNode::new_bst_nodelink(0);
+        empty.borrow_mut().key = None;
+        assert_eq!(BstNode::validate(&empty), Ok(()));
+        let thirteen = BstNode::resolve_link(&rootlink, &rootlink.borrow().tree_search(&13).unwrap());
+        thirteen.borrow_mut().key = Some(5);
+        assert!(matches!(BstNode::validate(&rootlink), Err(InvariantError::OutOfOrder { key: 5, .. })));
+        thirteen.borrow_mut().key = Some(13);
+        let nine = thirteen.borrow().left.clone().unwrap();
+        nine.borrow_mut().parent = Some(std::rc::Rc::downgrade(&rootlink));
+        assert_eq!(BstNode::validate(&rootlink), Err(InvariantError::WrongParent(9)));
+
+        //the tree agrees with BTreeSet on a few hundred seeded runs
+        for seed in 0..200 {
+            assert_eq!(check_seed(seed, 150, 40), Ok(()), "seed {}", seed);
+        }
+        let ops = generate(11, 50, 10);
+        assert_eq!(ops, generate(11, 50, 10));
+
+        //shrinking keeps only what the failure needs
+        let ops = generate(5, 300, 10);
+        let fails = |ops: &[Op]| {
+            let insert = ops.iter().position(|&op| op == Op::Insert(3));
+            insert.is_some_and(|at| ops[at..].contains(&Op::Delete(3)))
+        };
+        assert!(fails(&ops));
+        assert_eq!(shrink(ops, fails), vec![Op::Insert(3), Op::Delete(3)]);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::structure::bst::BstNodeLink;
+use crate::tool::cells::cells_of_bst;
+use crate::tool::{dot_text, print_child, Side};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+/**
+ * What changed between two BSTs, every list is in key order
+ */
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BstDiff {
+    //only in the after tree
+    pub added: Vec<i32>,
+    //only in the before tree
+    pub removed: Vec<i32>,
+    //in both trees under a different parent, becoming or stopping being the root counts too
+    pub moved: Vec<i32>,
+}
+
+const ADDED_COLOR: &str = "palegreen";
+const REMOVED_COLOR: &str = "lightgray";
+const MOVED_COLOR: &str = "salmon";
+
+//key -> parent key of every node, the root has no parent
+fn parents(root: &BstNodeLink) -> BTreeMap<i32, Option<i32>> {
+    let mut parents = BTreeMap::new();
+    let mut stack = vec![(root.clone(), None)];
+    while let Some((node, parent)) = stack.pop() {
+        let node = node.borrow();
+        let Some(key) = node.key else {
+            continue;
+        };
+        parents.insert(key, parent);
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            stack.push((child.clone(), Some(key)));
+        }
+    }
+    parents
+}
+
+/**
+ * Compare the trees by key, a node counts as moved when its parent key changed
+ */
+pub fn diff_bst(before: &BstNodeLink, after: &BstNodeLink) -> BstDiff {
+    let before_parents = parents(before);
+    let after_parents = parents(after);
+    let mut diff = BstDiff::default();
+    for (key, parent) in &before_parents {
+        match after_parents.get(key) {
+            None => diff.removed.push(*key),
+            Some(after_parent) if after_parent != parent => diff.moved.push(*key),
+            Some(_) => {}
+        }
+    }
+    diff.added = after_parents.keys().filter(|key| !before_parents.contains_key(key)).copied().collect();
+    diff
+}
+
+/**
+ * Write before and after as two clusters of one dot file, side by side
+ * Added keys are green in after, removed keys grey in before, moved keys salmon in both
+ * before has to be a separate tree, e.g: a copy taken with BstNode::from_preorder(&BstNode::preorder_keys(&root))
+ * since tree_delete changes the tree in place
+ */
+pub fn generate_dotfile_bst_diff(before: &BstNodeLink, after: &BstNodeLink, output_path: &str) -> io::Result<()> {
+    fs::write(output_path, dot_diff_text(before, after))
+}
+
+pub fn dot_diff_text(before: &BstNodeLink, after: &BstNodeLink) -> String {
+    let diff = diff_bst(before, after);
+    let mut graph_arrangement = cluster(before, "before", &[(&diff.removed, REMOVED_COLOR), (&diff.moved, MOVED_COLOR)]);
+    graph_arrangement += &cluster(after, "after", &[(&diff.added, ADDED_COLOR), (&diff.moved, MOVED_COLOR)]);
+    dot_text(&graph_arrangement)
+}
+
+//cluster name and pre-order number of the node, e.g: before_n3, the key is only its label
+fn node_name(cluster: &str, index: usize) -> String {
+    format!("{}_n{}", cluster, index)
+}
+
+//one subgraph, edges in the same order as generate_dotfile_bst then a statement for every node
+fn cluster(root: &BstNodeLink, name: &str, colors: &[(&Vec<i32>, &str)]) -> String {
+    let mut new_info = format!("\tsubgraph cluster_{}{{\n\t\tlabel=\"{}\";\n", name, name);
+    let cells = cells_of_bst(&root.borrow());
+    for (index, cell) in cells.iter().enumerate() {
+        let parent = node_name(name, index);
+        let left = cell.left.map(|left| node_name(name, left));
+        let right = cell.right.map(|right| node_name(name, right));
+        let edges = print_child(&parent, left.as_deref(), right.is_some(), Side::Left, false, None)
+            + &print_child(&parent, right.as_deref(), left.is_some(), Side::Right, false, None);
+        for line in edges.lines() {
+            new_info += &format!("\t{}\n", line);
+        }
+    }
+    if cells.is_empty() {
+        new_info += &format!("\t\t{}_empty [label=\"(empty)\", shape=plaintext];\n", name);
+    }
+    for (index, cell) in cells.iter().enumerate() {
+        let mut attributes = format!("label=\"{}\"", cell.label);
+        if let Some((_, color)) = colors.iter().find(|(keys, _)| keys.iter().any(|key| key.to_string() == cell.label)) {
+            attributes += &format!(", style=filled, fillcolor={}", color);
+        }
+        new_info += &format!("\t\t{} [{}];\n", node_name(name, index), attributes);
+    }
+    new_info + "\t}\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::{BstNode, BstNodeLink};
+    use crate::test_util::{demo_bst, temp_path};
+
+    #[test]
+    fn test_dot_diff() {
+        let rootlink = demo_bst();
+        let before = BstNode::from_preorder(&BstNode::preorder_keys(&rootlink)).unwrap();
+        assert_eq!(diff_bst(&before, &rootlink), BstDiff::default());
+
+        //deleting the root moves its successor 17 up, 18 and 6 now hang below 17 instead of 15
+        let fifteen = BstNode::resolve_link(&rootlink, &rootlink.borrow().tree_search(&15).unwrap());
+        BstNode::tree_delete(&mut rootlink.clone(), &fifteen);
+        BstNode::tree_insert(&rootlink, -1);
+        let diff = diff_bst(&before, &rootlink);
+        assert_eq!(
+            diff,
+            BstDiff {
+                added: vec![-1],
+                removed: vec![15],
+                moved: vec![6, 17, 18],
+            }
+        );
+
+        let dot_path = temp_path("dot_diff_test.dot");
+        let path = dot_path.to_str().unwrap();
+        generate_dotfile_bst_diff(&before, &rootlink, path).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        assert_eq!(text, dot_diff_text(&before, &rootlink));
+        //names are the cluster and the pre-order number, before: 15 6 3 2 4 7 13 9 18 17 20, after: 17 6 3 2 -1 4 7 13 9 18 20
+        assert!(text.starts_with("digraph tree{\n\tordering=out;\n\tsubgraph cluster_before{\n\t\tlabel=\"before\";\n\t\tbefore_n0->before_n1;\n"));
+        assert!(text.contains("\t\tbefore_n0 [label=\"15\", style=filled, fillcolor=lightgray];\n"));
+        assert!(text.contains("\t\tbefore_n9 [label=\"17\", style=filled, fillcolor=salmon];\n"));
+        assert!(text.contains("\t\tbefore_n2 [label=\"3\"];\n"));
+        assert!(text.contains("\tsubgraph cluster_after{\n\t\tlabel=\"after\";\n\t\tafter_n0->after_n1;\n"));
+        assert!(text.contains("\t\tafter_n3->after_n4;\n\t\t\"nil_after_n3_R\" [style=invis];\n"));
+        assert!(text.contains("\t\tafter_n4 [label=\"-1\", style=filled, fillcolor=palegreen];\n"));
+        assert!(text.contains("\t\tafter_n9 [label=\"18\", style=filled, fillcolor=salmon];\n"));
+        assert_eq!(text.matches("label=\"15\"").count(), 1);
+        assert!(text.ends_with("\t}\n}"));
+
+        //an empty side still gets a cluster
+        let empty: BstNodeLink = BstNode::new_bst_nodelink(1);
+        let one = empty.borrow().tree_search(&1).unwrap();
+        BstNode::tree_delete(&mut empty.clone(), &BstNode::resolve_link(&empty, &one));
+        let text = dot_diff_text(&empty, &before);
+        assert!(text.contains("\t\tbefore_empty [label=\"(empty)\", shape=plaintext];\n"));
+        assert_eq!(diff_bst(&empty, &before).added.len(), 11);
+        let _ = std::fs::remove_file(&dot_path);
+    }
+}
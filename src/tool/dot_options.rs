@@ -0,0 +1,255 @@
+use crate::structure::bst::BstNodeLink;
+use std::collections::HashMap;
+
+/**
+ * Options of every dot export (generate_dotfile_bst_styled, write_dot, write_dot_bst), styles are keyed by node key
+ * while the graphviz names are n0, n1, ... in pre-order, the key is only the label
+ * e.g: DotOptions::new().highlight_path(&root, 9, "gold").fill(15, "lightblue").annotate(Annotation::Depth)
+ */
+#[derive(Default, Clone)]
+pub struct DotOptions {
+    pub edge_labels: bool,
+    //nodes below this depth (root at 0) are left out, each node at max_depth with children gets
+    //one "… (n nodes)" placeholder instead, n counting everything below it
+    pub max_depth: Option<usize>,
+    pub nodes: HashMap<i32, NodeStyle>,
+    //raw graphviz attributes, e.g: "color=red, style=dashed"
+    pub edges: HashMap<(i32, i32), String>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Default, Clone)]
+pub struct NodeStyle {
+    pub fill: Option<String>,
+    //graphviz shape name, e.g: box, doublecircle
+    pub shape: Option<String>,
+    //shown instead of the key
+    pub label: Option<String>,
+}
+
+/**
+ * Extra lines printed inside every node label, below the key
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Annotation {
+    //edges from the root, the root is 0
+    Depth,
+    //nodes in the subtree, the node included
+    SubtreeSize,
+    //height of the left subtree minus height of the right one, a missing subtree counts as 0, a leaf as 1
+    BalanceFactor,
+}
+
+//per node numbers for the annotations
+pub(crate) struct Stats {
+    depth: usize,
+    size: usize,
+    height: usize,
+    balance: i64,
+}
+
+impl DotOptions {
+    pub fn new() -> DotOptions {
+        DotOptions::default()
+    }
+
+    //L / R label on every edge
+    pub fn edge_labels(mut self) -> DotOptions {
+        self.edge_labels = true;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> DotOptions {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn fill(mut self, key: i32, color: &str) -> DotOptions {
+        self.nodes.entry(key).or_default().fill = Some(color.to_string());
+        self
+    }
+
+    pub fn shape(mut self, key: i32, shape: &str) -> DotOptions {
+        self.nodes.entry(key).or_default().shape = Some(shape.to_string());
+        self
+    }
+
+    pub fn label(mut self, key: i32, label: &str) -> DotOptions {
+        self.nodes.entry(key).or_default().label = Some(label.to_string());
+        self
+    }
+
+    pub fn edge_style(mut self, parent: i32, child: i32, attributes: &str) -> DotOptions {
+        self.edges.insert((parent, child), attributes.to_string());
+        self
+    }
+
+    pub fn annotate(mut self, annotation: Annotation) -> DotOptions {
+        if !self.annotations.contains(&annotation) {
+            self.annotations.push(annotation);
+        }
+        self
+    }
+
+    /**
+     * Fill every node tree_search visits on its way to key and draw the edges between them bold
+     * If key isn't in the tree the path stops at the node where the search falls off
+     */
+    pub fn highlight_path(mut self, root: &BstNodeLink, key: i32, color: &str) -> DotOptions {
+        let mut previous: Option<i32> = None;
+        let mut next = Some(root.clone());
+        while let Some(current) = next {
+            let Some(current_key) = current.borrow().key else {
+                break;
+            };
+            self = self.fill(current_key, color);
+            if let Some(parent) = previous {
+                self = self.edge_style(parent, current_key, &format!("color={}, penwidth=2", color));
+            }
+            previous = Some(current_key);
+            next = if key == current_key {
+                None
+            } else if key < current_key {
+                current.borrow().left.clone()
+            } else {
+                current.borrow().right.clone()
+            };
+        }
+        self
+    }
+
+    /**
+     * Depth, size, height and balance of every node, depth top-down then the rest bottom-up
+     * Only computed when there are annotations to show, empty otherwise
+     */
+    pub(crate) fn stats(&self, root: &BstNodeLink) -> HashMap<i32, Stats> {
+        if self.annotations.is_empty() {
+            return HashMap::new();
+        }
+        let mut order: Vec<(BstNodeLink, usize)> = Vec::new();
+        let mut stack = vec![(root.clone(), 0)];
+        while let Some((node, depth)) = stack.pop() {
+            for child in [&node.borrow().left, &node.borrow().right].into_iter().flatten() {
+                stack.push((child.clone(), depth + 1));
+            }
+            order.push((node, depth));
+        }
+        let mut stats: HashMap<i32, Stats> = HashMap::new();
+        //children are always after their parent in order, so walking it backwards sees them first
+        for (node, depth) in order.iter().rev() {
+            let node = node.borrow();
+            let Some(key) = node.key else {
+                continue;
+            };
+            let child = |child: &Option<BstNodeLink>| {
+                child
+                    .as_ref()
+                    .and_then(|child| child.borrow().key)
+                    .and_then(|child_key| stats.get(&child_key))
+                    .map_or((0, 0), |child_stats| (child_stats.size, child_stats.height))
+            };
+            let (left_size, left_height) = child(&node.left);
+            let (right_size, right_height) = child(&node.right);
+            stats.insert(
+                key,
+                Stats {
+                    depth: *depth,
+                    size: left_size + right_size + 1,
+                    height: left_height.max(right_height) + 1,
+                    balance: left_height as i64 - right_height as i64,
+                },
+            );
+        }
+        stats
+    }
+
+    /**
+     * Attribute list of the node holding key, the label comes first and is just the key for a node with
+     * neither a custom label nor annotations, e.g: label=15, style=filled, fillcolor="gold"
+     * node_stats comes from stats and is only there when annotations are asked for
+     */
+    pub(crate) fn node_attributes(&self, key: i32, node_stats: Option<&Stats>) -> String {
+        let style = self.nodes.get(&key).cloned().unwrap_or_default();
+        let mut lines = vec![style.label.clone().unwrap_or(key.to_string())];
+        if let Some(node_stats) = node_stats {
+            for annotation in &self.annotations {
+                lines.push(match annotation {
+                    Annotation::Depth => format!("depth {}", node_stats.depth),
+                    Annotation::SubtreeSize => format!("size {}", node_stats.size),
+                    Annotation::BalanceFactor => format!("bf {}", node_stats.balance),
+                });
+            }
+        }
+        let mut attributes: Vec<String> = Vec::new();
+        if style.label.is_some() || lines.len() > 1 {
+            let lines: Vec<String> = lines.iter().map(|line| line.replace('"', "\\\"")).collect();
+            attributes.push(format!("label=\"{}\"", lines.join("\\n")));
+        } else {
+            attributes.push(format!("label={}", key));
+        }
+        if let Some(fill) = &style.fill {
+            attributes.push(format!("style=filled, fillcolor=\"{}\"", fill));
+        }
+        if let Some(shape) = &style.shape {
+            attributes.push(format!("shape={}", shape));
+        }
+        attributes.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Annotation, DotOptions};
+    use crate::test_util::{demo_bst, temp_path};
+    use crate::tool::{dot_reader, generate_dotfile_bst_styled};
+
+    #[test]
+    fn test_dot_options() {
+        let rootlink = demo_bst();
+        let dot_path = temp_path("dot_options_test.dot");
+        let path = dot_path.to_str().unwrap();
+
+        //search path to 9 is 15, 6, 7, 13, 9
+        let options = DotOptions::new().highlight_path(&rootlink, 9, "gold").shape(20, "box").label(2, "two \"2\"");
+        generate_dotfile_bst_styled(&rootlink, path, &options).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        //names are pre-order numbers (15 6 3 2 4 7 13 9 18 17 20), styles still go by key
+        assert!(text.contains("\tn0->n1 [color=gold, penwidth=2];\n"));
+        assert!(text.contains("\tn6->n7 [color=gold, penwidth=2];\n"));
+        assert!(text.contains("\tn0->n8;\n"));
+        assert!(text.contains("\tn7 [label=9, style=filled, fillcolor=\"gold\"];\n"));
+        assert!(text.contains("\tn8 [label=18];\n"));
+        assert!(text.contains("\tn10 [label=20, shape=box];\n"));
+        assert!(text.contains("\tn3 [label=\"two \\\"2\\\"\"];\n"));
+        assert!(text.ends_with("}"));
+        //a missing key stops the path where the search falls off
+        let options = DotOptions::new().highlight_path(&rootlink, 5, "red");
+        let mut filled: Vec<i32> = options.nodes.keys().copied().collect();
+        filled.sort();
+        assert_eq!(filled, vec![3, 4, 6, 15]);
+        assert_eq!(options.edges.len(), 3);
+        //styles for keys that are not in the tree are dropped, not written as stray nodes
+        generate_dotfile_bst_styled(&rootlink, path, &options.fill(99, "red").edge_style(4, 99, "color=red")).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        assert!(!text.contains("99"));
+        assert_eq!(text.matches("fillcolor=\"red\"").count(), 4);
+        assert_eq!(text.matches("color=red, penwidth=2").count(), 3);
+
+        let options = DotOptions::new()
+            .annotate(Annotation::Depth)
+            .annotate(Annotation::SubtreeSize)
+            .annotate(Annotation::BalanceFactor)
+            .fill(15, "lightblue")
+            .edge_style(18, 20, "style=dashed")
+            .edge_labels();
+        generate_dotfile_bst_styled(&rootlink, path, &options).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        assert!(text.contains("\tn0 [label=\"15\\ndepth 0\\nsize 11\\nbf 2\", style=filled, fillcolor=\"lightblue\"];\n"));
+        assert!(text.contains("\tn5 [label=\"7\\ndepth 2\\nsize 3\\nbf -2\"];\n"));
+        assert!(text.contains("\tn7 [label=\"9\\ndepth 4\\nsize 1\\nbf 0\"];\n"));
+        assert!(text.contains("\tn8->n10 [label=R, style=dashed];\n"));
+        //annotated labels keep the key on their first line, so the export still reads back
+        assert_eq!(dot_reader::read_dotfile_bst(path).unwrap(), rootlink);
+        let _ = std::fs::remove_file(&dot_path);
+    }
+}
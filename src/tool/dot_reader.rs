@@ -0,0 +1,558 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::NodeLink;
+use crate::tool::layout::TreeLayout;
+use crate::tool::tree_text;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+/**
+ * Reader for the dot subset written by generate_dotfile and generate_dotfile_bst
+ * e.g: graph tree{ 15--6; 15--18; }
+ * digraph, edge chains (a -> b -> c), attribute lists, quoted ids and node labels are accepted as well
+ * For a generic tree the side of a child is taken from, in order of priority:
+ * 1). an edge label L / R (or left / right),
+ * 2). the tail port compass point of the parent (15:sw -> 6, 15:se -> 18),
+ * 3). the edge order, first child goes left and the second one right
+ * The placeholders generate_dotfile writes for a missing child (an invisible node named nil_<parent>_<L|R>)
+ * keep their slot while the sides are decided and are dropped afterwards, any other node is a real one
+ * An empty graph reads back as an empty BST (key None)
+ * The "Tree Node { P88 -> L48; }" text format of prime.dot and friends is accepted too and handed over to tree_text
+ */
+pub fn read_dotfile(input_path: &str) -> Result<NodeLink> {
+    parse_dot(&fs::read_to_string(input_path)?)
+}
+
+/**
+ * Same as read_dotfile but the side of every child is decided by key order
+ */
+pub fn read_dotfile_bst(input_path: &str) -> Result<BstNodeLink> {
+    parse_dot_bst(&fs::read_to_string(input_path)?)
+}
+
+pub fn parse_dot(text: &str) -> Result<NodeLink> {
+    if is_tree_text(text) {
+        return tree_text::tree_from_text(text);
+    }
+    let graph = DotGraph::parse(text)?;
+    let layout = graph.layout(|_, _, hint| hint)?;
+    Ok(layout.build_tree())
+}
+
+pub fn parse_dot_bst(text: &str) -> Result<BstNodeLink> {
+    if is_tree_text(text) {
+        return tree_text::bst_from_text(text);
+    }
+    let graph = DotGraph::parse(text)?;
+    if graph.names.is_empty() {
+        let empty = BstNode::new_bst_nodelink(0);
+        empty.borrow_mut().key = None;
+        return Ok(empty);
+    }
+    let layout = graph.layout(|parent, child, _| {
+        if child < parent {
+            Some(Side::Left)
+        } else if child > parent {
+            Some(Side::Right)
+        } else {
+            None
+        }
+    })?;
+    let root = layout.build_bst();
+    //key order only holds locally so far, check it for the whole tree
+    let keys = BstNode::inorder_keys(&root);
+    if keys.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(invalid_data("graph is not a binary search tree".to_string()));
+    }
+    Ok(root)
+}
+
+fn is_tree_text(text: &str) -> bool {
+    text.trim_start().starts_with(tree_text::HEADER)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(String),
+    EdgeOp,
+    Symbol(char),
+}
+
+struct DotEdge {
+    parent: usize,
+    child: usize,
+    hint: Option<Side>,
+}
+
+//nodes are stored in order of first appearance, edges keep their order in the file
+#[derive(Default)]
+struct DotGraph {
+    names: Vec<String>,
+    labels: HashMap<usize, String>,
+    edges: Vec<DotEdge>,
+    //nodes with style=invis, only the ones named like a placeholder are dropped
+    invisible: HashSet<usize>,
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && next == Some('/') || c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '-' && (next == Some('-') || next == Some('>')) {
+            tokens.push(Token::EdgeOp);
+            i += 2;
+        } else if c == '"' {
+            let mut value = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                //only \" is an escape, label escapes like \n stay as they are
+                if chars[i] == '\\' && chars.get(i + 1) == Some(&'"') {
+                    i += 1;
+                }
+                value.push(chars[i]);
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(invalid_data("unterminated string".to_string()));
+            }
+            tokens.push(Token::Id(value));
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Id(chars[start..i].iter().collect()));
+        } else if "{}[]=;,:".contains(c) {
+            tokens.push(Token::Symbol(c));
+            i += 1;
+        } else {
+            return Err(invalid_data(format!("unexpected character '{}'", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+//recursive descent over the token list, only the parts that matter for a tree are kept
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    graph: DotGraph,
+    ids: HashMap<String, usize>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat_symbol(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_id(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Id(id)) => Ok(id),
+            other => Err(invalid_data(format!("expected an identifier, found {:?}", other))),
+        }
+    }
+
+    fn node_index(&mut self, name: &str) -> usize {
+        if let Some(index) = self.ids.get(name) {
+            return *index;
+        }
+        let index = self.graph.names.len();
+        self.graph.names.push(name.to_string());
+        self.ids.insert(name.to_string(), index);
+        index
+    }
+
+    fn parse_graph(&mut self) -> Result<()> {
+        let mut keyword = self.expect_id()?;
+        if keyword.eq_ignore_ascii_case("strict") {
+            keyword = self.expect_id()?;
+        }
+        if !keyword.eq_ignore_ascii_case("graph") && !keyword.eq_ignore_ascii_case("digraph") {
+            return Err(invalid_data(format!("expected graph or digraph, found {}", keyword)));
+        }
+        if matches!(self.peek(), Some(Token::Id(_))) {
+            self.position += 1;
+        }
+        if !self.eat_symbol('{') {
+            return Err(invalid_data("expected '{' after the graph header".to_string()));
+        }
+        self.parse_statements()?;
+        if self.peek().is_some() {
+            return Err(invalid_data("unexpected input after the closing '}'".to_string()));
+        }
+        Ok(())
+    }
+
+    //statements until the matching '}', subgraph blocks are flattened into the parent graph
+    fn parse_statements(&mut self) -> Result<()> {
+        loop {
+            match self.peek().cloned() {
+                None => return Err(invalid_data("missing closing '}'".to_string())),
+                Some(Token::Symbol('}')) => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                Some(Token::Symbol(';')) | Some(Token::Symbol(',')) => self.position += 1,
+                Some(Token::Symbol('{')) => {
+                    self.position += 1;
+                    self.parse_statements()?;
+                }
+                Some(Token::Id(id)) if id.eq_ignore_ascii_case("subgraph") => {
+                    self.position += 1;
+                    if matches!(self.peek(), Some(Token::Id(_))) {
+                        self.position += 1;
+                    }
+                }
+                Some(Token::Id(id))
+                    if ["graph", "node", "edge"].iter().any(|k| id.eq_ignore_ascii_case(k)) =>
+                {
+                    self.position += 1;
+                    self.parse_attributes()?;
+                }
+                Some(Token::Id(_)) => self.parse_node_or_edge()?,
+                Some(other) => return Err(invalid_data(format!("unexpected {:?}", other))),
+            }
+        }
+    }
+
+    fn parse_attributes(&mut self) -> Result<Vec<(String, String)>> {
+        let mut attributes = Vec::new();
+        while self.eat_symbol('[') {
+            while !self.eat_symbol(']') {
+                if self.eat_symbol(',') || self.eat_symbol(';') {
+                    continue;
+                }
+                let name = self.expect_id()?;
+                let mut value = String::new();
+                if self.eat_symbol('=') {
+                    value = self.expect_id()?;
+                }
+                attributes.push((name, value));
+            }
+        }
+        Ok(attributes)
+    }
+
+    //node_id [':' port [':' compass]], returns the node and the compass point if any
+    fn parse_node_id(&mut self) -> Result<(usize, Option<String>)> {
+        let name = self.expect_id()?;
+        let index = self.node_index(&name);
+        let mut port = None;
+        while self.eat_symbol(':') {
+            port = Some(self.expect_id()?);
+        }
+        Ok((index, port))
+    }
+
+    fn parse_node_or_edge(&mut self) -> Result<()> {
+        //graph level attribute like rankdir=TB or ordering=out, not a node
+        if self.tokens.get(self.position + 1) == Some(&Token::Symbol('=')) {
+            self.position += 2;
+            self.expect_id()?;
+            return Ok(());
+        }
+        let (first, first_port) = self.parse_node_id()?;
+        let mut chain = vec![(first, first_port)];
+        while self.peek() == Some(&Token::EdgeOp) {
+            self.position += 1;
+            chain.push(self.parse_node_id()?);
+        }
+        let attributes = self.parse_attributes()?;
+        let invisible = attributes.iter().any(|(name, value)| name == "style" && value == "invis");
+        if invisible {
+            self.graph.invisible.insert(chain[chain.len() - 1].0);
+        }
+        if chain.len() == 1 {
+            if let Some((_, label)) = attributes.iter().find(|(name, _)| name == "label") {
+                self.graph.labels.insert(first, label.clone());
+            }
+            return Ok(());
+        }
+        let label_hint = attributes
+            .iter()
+            .find(|(name, _)| name == "label" || name == "taillabel")
+            .and_then(|(_, value)| side_from_name(value));
+        for pair in chain.windows(2) {
+            let port_hint = pair[0].1.as_deref().and_then(side_from_name);
+            self.graph.edges.push(DotEdge {
+                parent: pair[0].0,
+                child: pair[1].0,
+                hint: label_hint.or(port_hint),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn side_from_name(name: &str) -> Option<Side> {
+    match name.to_ascii_lowercase().as_str() {
+        "l" | "left" | "sw" | "w" => Some(Side::Left),
+        "r" | "right" | "se" | "e" => Some(Side::Right),
+        _ => None,
+    }
+}
+
+impl DotGraph {
+    fn parse(text: &str) -> Result<DotGraph> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            graph: DotGraph::default(),
+            ids: HashMap::new(),
+        };
+        parser.parse_graph()?;
+        Ok(parser.graph)
+    }
+
+    //a multi line label (e.g: "15\ndepth 0" from the styled export) holds the value on its first line
+    fn value_of(&self, index: usize) -> Result<i32> {
+        let text = self.labels.get(&index).unwrap_or(&self.names[index]);
+        let text = text.split("\\n").next().unwrap_or_default();
+        text.trim()
+            .parse::<i32>()
+            .map_err(|_| invalid_data(format!("node '{}' has no integer value", text)))
+    }
+
+    /**
+     * Placeholders with the side their name gives them: invisible and named nil_<parent>_<L|R> after the parent
+     * of the edge leading to them, e.g: "nil_n5_L" below n5
+     */
+    fn placeholders(&self) -> HashMap<usize, Side> {
+        let mut placeholders = HashMap::new();
+        for edge in &self.edges {
+            if !self.invisible.contains(&edge.child) {
+                continue;
+            }
+            let name = &self.names[edge.child];
+            let side = name
+                .strip_prefix("nil_")
+                .and_then(|rest| rest.strip_prefix(self.names[edge.parent].as_str()))
+                .and_then(|rest| match rest {
+                    "_L" => Some(Side::Left),
+                    "_R" => Some(Side::Right),
+                    _ => None,
+                });
+            if let Some(side) = side {
+                placeholders.insert(edge.child, side);
+            }
+        }
+        placeholders
+    }
+
+    /**
+     * Resolve every edge to a left or right slot and find the root
+     * choose_side gets (parent value, child value, hint from the file) and may return None to fall back to edge order
+     */
+    fn layout<F>(&self, choose_side: F) -> Result<TreeLayout>
+    where
+        F: Fn(i32, i32, Option<Side>) -> Option<Side>,
+    {
+        if self.names.is_empty() {
+            return Err(invalid_data("graph has no nodes".to_string()));
+        }
+        let hidden = self.placeholders();
+        let values = (0..self.names.len())
+            .map(|index| if hidden.contains_key(&index) { Ok(0) } else { self.value_of(index) })
+            .collect::<Result<Vec<i32>>>()?;
+        let mut parents: Vec<Option<usize>> = vec![None; self.names.len()];
+        let mut children: Vec<[Option<usize>; 2]> = vec![[None, None]; self.names.len()];
+        let mut unplaced: Vec<&DotEdge> = Vec::new();
+        for edge in &self.edges {
+            if let Some(previous) = parents[edge.child] {
+                return Err(invalid_data(format!(
+                    "node '{}' has two parents ('{}' and '{}')",
+                    self.names[edge.child], self.names[previous], self.names[edge.parent]
+                )));
+            }
+            parents[edge.child] = Some(edge.parent);
+            //a placeholder has no value to compare, its name tells the side
+            let side = if let Some(side) = hidden.get(&edge.child) {
+                Some(*side)
+            } else {
+                choose_side(values[edge.parent], values[edge.child], edge.hint)
+            };
+            match side {
+                Some(side) => {
+                    let slot = &mut children[edge.parent][side as usize];
+                    if slot.is_some() {
+                        return Err(invalid_data(format!(
+                            "node '{}' has two {:?} children",
+                            self.names[edge.parent], side
+                        )));
+                    }
+                    *slot = Some(edge.child);
+                }
+                None => unplaced.push(edge),
+            }
+        }
+        //edges without a decided side fill the remaining slots in file order
+        for edge in unplaced {
+            match children[edge.parent].iter_mut().find(|slot| slot.is_none()) {
+                Some(slot) => *slot = Some(edge.child),
+                None => {
+                    return Err(invalid_data(format!(
+                        "node '{}' has more than two children",
+                        self.names[edge.parent]
+                    )))
+                }
+            }
+        }
+        //the placeholders did their job, free their slots
+        for slots in children.iter_mut() {
+            for slot in slots.iter_mut() {
+                if slot.is_some_and(|child| hidden.contains_key(&child)) {
+                    *slot = None;
+                }
+            }
+        }
+        let roots: Vec<usize> = (0..self.names.len())
+            .filter(|&i| parents[i].is_none() && !hidden.contains_key(&i))
+            .collect();
+        if roots.len() != 1 {
+            return Err(invalid_data(format!("expected exactly one root, found {}", roots.len())));
+        }
+        //children are resolved to a side and indexed the same way as names
+        let layout = TreeLayout {
+            values,
+            children,
+            root: roots[0],
+        };
+        if layout.reachable() != self.names.len() - hidden.len() {
+            return Err(invalid_data("graph contains a cycle".to_string()));
+        }
+        Ok(layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::{BstNode, BstNodeLink};
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::{chain, demo_bst, temp_path};
+    use crate::tool::dot_options::DotOptions;
+    use crate::tool::dot_writer::write_dot_bst;
+    use crate::tool::{generate_dotfile, generate_dotfile_bst, serialize, tree_text};
+
+    #[test]
+    fn test_dot_reader() {
+        let rootlink = demo_bst();
+        //round trip through the exporter, 13 is a lone right child of 7 and has to come back on the right
+        let dot_path = temp_path("reader_test.dot");
+        let dot_path = dot_path.to_str().unwrap();
+        generate_dotfile_bst(&rootlink, dot_path).unwrap();
+        let restored = read_dotfile_bst(dot_path).unwrap();
+        assert_eq!(serialize::bst_to_json(&restored), serialize::bst_to_json(&rootlink));
+        let nine = restored.borrow().tree_search(&9).unwrap();
+        let nine_parent = BstNode::upgrade_weak_to_strong(nine.borrow().parent.clone()).unwrap();
+        assert_eq!(nine_parent.borrow().key, Some(13));
+
+        let treelink: NodeLink = Node::new_nodelink(5);
+        treelink.borrow_mut().add_left_child(&treelink, 3);
+        treelink.borrow_mut().add_right_child(&treelink, 7);
+        generate_dotfile(&treelink, dot_path).unwrap();
+        let restored = read_dotfile(dot_path).unwrap();
+        let _ = std::fs::remove_file(dot_path);
+        assert_eq!(serialize::tree_to_json(&restored), serialize::tree_to_json(&treelink));
+
+        //generic trees follow labels, then ports, then edge order
+        let text = "digraph G {\n  node [shape=circle];\n  5 -> 3 [label=R];\n  5 -> 7;\n  7:sw -> 10;\n}\n";
+        let treelink = parse_dot(text).unwrap();
+        assert_eq!(treelink.borrow().value, 5);
+        assert_eq!(treelink.borrow().left.as_ref().unwrap().borrow().value, 7);
+        assert_eq!(treelink.borrow().right.as_ref().unwrap().borrow().value, 3);
+        let seven = treelink.borrow().left.clone().unwrap();
+        assert_eq!(seven.borrow().left.as_ref().unwrap().borrow().value, 10);
+        assert!(seven.borrow().right.is_none());
+
+        //quoted ids with labels and edge chains
+        let text = "graph { \"a\" [label=\"2\"]; b [label=1]; c [label=4]; a -- c -- d; a -- b; d [label=3] }";
+        let restored = parse_dot_bst(text).unwrap();
+        assert_eq!(BstNode::inorder_keys(&restored), vec![1, 2, 3, 4]);
+
+        assert!(parse_dot("graph { 1--2; 3--2; }").is_err());
+        assert!(parse_dot("graph { 1--2; 2--1; }").is_err());
+        assert!(parse_dot("graph { 1--2; 1--3; 1--4; }").is_err());
+        assert!(parse_dot_bst("graph { 5--3; 3--6; }").is_err());
+        assert!(parse_dot("graph { a--b; }").is_err());
+        assert!(parse_dot("graph { 1--2; ").is_err());
+
+        //a lone root and an empty tree read back too
+        let single: BstNodeLink = BstNode::new_bst_nodelink(42);
+        generate_dotfile_bst(&single, dot_path).unwrap();
+        assert_eq!(read_dotfile_bst(dot_path).unwrap(), single);
+        let mut dot = Vec::new();
+        write_dot_bst(&single, &mut dot, &DotOptions::default()).unwrap();
+        assert_eq!(parse_dot_bst(&String::from_utf8(dot).unwrap()).unwrap(), single);
+        let empty: BstNodeLink = BstNode::new_bst_nodelink(0);
+        empty.borrow_mut().key = None;
+        generate_dotfile_bst(&empty, dot_path).unwrap();
+        assert_eq!(read_dotfile_bst(dot_path).unwrap().borrow().key, None);
+        //only invisible nodes named after their parent are placeholders, any other invisible node is kept
+        let restored = parse_dot("digraph { 5 -> 3; 5 -> 8 [style=invis]; 8 [style=invis]; }").unwrap();
+        assert_eq!(restored.borrow().right.as_ref().unwrap().borrow().value, 8);
+        let restored = parse_dot("digraph { 5 -> \"nil_5_L\"; \"nil_5_L\" [style=invis]; 5 -> 8; }").unwrap();
+        assert!(restored.borrow().left.is_none());
+        assert_eq!(restored.borrow().right.as_ref().unwrap().borrow().value, 8);
+        assert!(parse_dot("digraph { 5 -> \"nil_6_L\"; \"nil_6_L\" [style=invis]; 5 -> 8; }").is_err());
+        //the Tree Node text format of prime.dot is handed over to tree_text
+        let prime = read_dotfile(concat!(env!("CARGO_MANIFEST_DIR"), "/prime.dot")).unwrap();
+        assert_eq!(Node::preorder_values(&prime), vec![88, 48, 888]);
+        let prime = read_dotfile(concat!(env!("CARGO_MANIFEST_DIR"), "/prime_t3")).unwrap();
+        assert_eq!(prime, tree_text::read_treefile(concat!(env!("CARGO_MANIFEST_DIR"), "/prime_t3")).unwrap());
+        let prime = read_dotfile_bst(concat!(env!("CARGO_MANIFEST_DIR"), "/prime.dot")).unwrap();
+        assert_eq!(BstNode::preorder_keys(&prime), vec![88, 48, 888]);
+        //84 sits below 888 on the right of 88, so prime_t2 is a tree but not a BST
+        assert!(read_dotfile_bst(concat!(env!("CARGO_MANIFEST_DIR"), "/prime_t2")).is_err());
+
+        //a long chain reads back without recursing, both through dot and through the text format
+        let chain = chain(50_000);
+        let mut dot = Vec::new();
+        write_dot_bst(&chain, &mut dot, &DotOptions::default()).unwrap();
+        let restored = parse_dot_bst(&String::from_utf8(dot).unwrap()).unwrap();
+        assert!(*restored.borrow() == *chain.borrow());
+        let restored = tree_text::bst_from_text(&tree_text::bst_to_text(&chain)).unwrap();
+        assert!(*restored.borrow() == *chain.borrow());
+    }
+}
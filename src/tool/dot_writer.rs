@@ -0,0 +1,335 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::error::{try_read, BstError};
+use crate::structure::tree::{Node, NodeLink};
+use crate::tool::dot_options::{DotOptions, Stats};
+use crate::tool::{print_child, Side, DOT_EPILOGUE, DOT_PREAMBLE};
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/**
+ * The graph of generate_dotfile written piece by piece to any output, generate_dotfile is this on a file
+ * e.g: write_dot_bst(&root, &mut BufWriter::new(file), &DotOptions::new().max_depth(6))
+ * Nodes are named n0, n1, ... in pre-order and labeled with their value, so the same tree always gives the same text
+ * and equal values still get separate nodes. A name is a position, not an identity: it is unique within one graph,
+ * but after an insert or delete the same node can get another name, match nodes across snapshots by label instead
+ * tree::Node values may repeat, so of the options only edge_labels and max_depth apply to it
+ * A node that is already borrowed panics like borrow() would, try_write_dot reports it instead
+ */
+pub fn write_dot(root: &NodeLink, output: &mut impl Write, options: &DotOptions) -> io::Result<()> {
+    expect_io(try_write_dot(root, output, options))
+}
+
+/**
+ * The graph of generate_dotfile_bst / generate_dotfile_bst_styled written piece by piece to any output
+ * Named in pre-order like write_dot and labeled with the key, styles and annotations go in the node statement
+ * A node that is already borrowed or a child without key panics, try_write_dot_bst reports them instead
+ */
+pub fn write_dot_bst(root: &BstNodeLink, output: &mut impl Write, options: &DotOptions) -> io::Result<()> {
+    expect_io(try_write_dot_bst(root, output, options))
+}
+
+//only the write errors are the caller's to handle, anything else is a broken tree
+fn expect_io(result: Result<(), BstError>) -> io::Result<()> {
+    match result {
+        Ok(()) => Ok(()),
+        Err(BstError::Io(error)) => Err(error),
+        Err(error) => panic!("{}", error),
+    }
+}
+
+/**
+ * write_dot that reports a node already borrowed or a failing write as BstError instead of panicking
+ */
+pub fn try_write_dot(root: &NodeLink, output: &mut impl Write, options: &DotOptions) -> Result<(), BstError> {
+    walk(root, output, options, None)
+}
+
+/**
+ * write_dot_bst that reports a node already borrowed, a child without key or a failing write as BstError
+ */
+pub fn try_write_dot_bst(root: &BstNodeLink, output: &mut impl Write, options: &DotOptions) -> Result<(), BstError> {
+    let stats = options.stats(root);
+    walk(root, output, options, Some(&stats))
+}
+
+type Link<T> = Rc<RefCell<T>>;
+
+//what the walker needs from either node type
+trait DotNode: Sized {
+    fn read(link: &Link<Self>) -> Result<Ref<'_, Self>, BstError>;
+    fn children(&self) -> [Option<Link<Self>>; 2];
+    //None only for a BST node without key
+    fn value(&self) -> Option<i32>;
+}
+
+impl DotNode for Node {
+    fn read(link: &NodeLink) -> Result<Ref<'_, Node>, BstError> {
+        link.try_borrow().map_err(|_| BstError::BorrowConflict)
+    }
+
+    fn children(&self) -> [Option<NodeLink>; 2] {
+        [self.left.clone(), self.right.clone()]
+    }
+
+    fn value(&self) -> Option<i32> {
+        Some(self.value)
+    }
+}
+
+impl DotNode for BstNode {
+    fn read(link: &BstNodeLink) -> Result<Ref<'_, BstNode>, BstError> {
+        try_read(link)
+    }
+
+    fn children(&self) -> [Option<BstNodeLink>; 2] {
+        [self.left.clone(), self.right.clone()]
+    }
+
+    fn value(&self) -> Option<i32> {
+        self.key
+    }
+}
+
+//the edge leading to a node, written once the node has its number
+struct Edge {
+    parent: String,
+    parent_value: i32,
+    side: Side,
+    sibling_exists: bool,
+}
+
+/**
+ * One pre-order pass that numbers the nodes as it goes, every node writes its own statement
+ * and then the edge from its parent, so no child has to be looked at before its turn
+ * The invisible placeholder of a missing left child goes right before the edge to the right one,
+ * that of a missing right child right after the edge to the left one, keeping the left edge first
+ * styles is there for the BST export only, then options style the nodes and edges by key
+ * An empty BST root (key None) writes an empty graph
+ */
+fn walk<T: DotNode>(
+    root: &Link<T>,
+    output: &mut impl Write,
+    options: &DotOptions,
+    styles: Option<&HashMap<i32, Stats>>,
+) -> Result<(), BstError> {
+    output.write_all(DOT_PREAMBLE.as_bytes())?;
+    let mut number = 0;
+    let mut stack: Vec<(Link<T>, usize, Option<Edge>)> = vec![(root.clone(), 0, None)];
+    while let Some((current, depth, edge)) = stack.pop() {
+        let current_ref = T::read(&current)?;
+        let value = match (current_ref.value(), &edge) {
+            (Some(value), _) => value,
+            (None, None) => continue,
+            (None, Some(_)) => return Err(BstError::EmptyTree),
+        };
+        let name = format!("n{}", number);
+        number += 1;
+        let attributes = match styles {
+            Some(stats) => options.node_attributes(value, stats.get(&value)),
+            None => format!("label={}", value),
+        };
+        writeln!(output, "\t{} [{}];", name, attributes)?;
+        if let Some(edge) = edge {
+            let attributes = styles.and_then(|_| options.edges.get(&(edge.parent_value, value)));
+            write_edge(output, &edge, &name, options.edge_labels, attributes.map(String::as_str))?;
+        }
+        let [left, right] = current_ref.children();
+        if options.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            let mut hidden = 0;
+            for child in [&left, &right].into_iter().flatten() {
+                hidden += count(child)?;
+            }
+            write_placeholder(output, &name, hidden)?;
+            continue;
+        }
+        let child_edge = |side, sibling: &Option<Link<T>>| Edge {
+            parent: name.clone(),
+            parent_value: value,
+            side,
+            sibling_exists: sibling.is_some(),
+        };
+        let right_edge = child_edge(Side::Right, &left);
+        let left_edge = child_edge(Side::Left, &right);
+        stack.extend(right.map(|right| (right, depth + 1, Some(right_edge))));
+        stack.extend(left.map(|left| (left, depth + 1, Some(left_edge))));
+    }
+    output.write_all(DOT_EPILOGUE.as_bytes())?;
+    Ok(())
+}
+
+//number of nodes below and including node
+fn count<T: DotNode>(node: &Link<T>) -> Result<usize, BstError> {
+    let mut count = 0;
+    let mut stack = vec![node.clone()];
+    while let Some(current) = stack.pop() {
+        count += 1;
+        stack.extend(T::read(&current)?.children().into_iter().flatten());
+    }
+    Ok(count)
+}
+
+//the edge to child together with the placeholder of its sibling when that one is missing, attributes go on the edge
+fn write_edge(output: &mut impl Write, edge: &Edge, child: &str, edge_labels: bool, attributes: Option<&str>) -> io::Result<()> {
+    let parent = edge.parent.as_str();
+    let child_edge = print_child(parent, Some(child), edge.sibling_exists, edge.side, edge_labels, attributes);
+    if edge.sibling_exists {
+        return output.write_all(child_edge.as_bytes());
+    }
+    match edge.side {
+        Side::Left => {
+            output.write_all(child_edge.as_bytes())?;
+            output.write_all(print_child(parent, None, true, Side::Right, edge_labels, None).as_bytes())
+        }
+        Side::Right => {
+            output.write_all(print_child(parent, None, true, Side::Left, edge_labels, None).as_bytes())?;
+            output.write_all(child_edge.as_bytes())
+        }
+    }
+}
+
+//a single box standing in for everything below parent, nothing is written for a leaf
+fn write_placeholder(output: &mut impl Write, parent: &str, hidden: usize) -> io::Result<()> {
+    if hidden == 0 {
+        return Ok(());
+    }
+    writeln!(output, "\t\"more_{}\" [label=\"… ({} nodes)\", shape=box, style=dashed];", parent, hidden)?;
+    writeln!(output, "\t{}->\"more_{}\" [style=dashed];", parent, parent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_dot, write_dot_bst};
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::{chain, demo_bst, temp_path};
+    use crate::tool::dot_options::DotOptions;
+    use crate::tool::{
+        dot_reader, generate_dotfile, generate_dotfile_bst, generate_dotfile_bst_labeled, generate_dotfile_bst_styled,
+        generate_dotfile_labeled,
+    };
+
+    #[test]
+    fn test_dot_identity() {
+        //5 with a 5 on each side, by value all three would be the same graphviz node
+        let treelink: NodeLink = Node::new_nodelink(5);
+        treelink.borrow_mut().add_left_child(&treelink, 5);
+        treelink.borrow_mut().add_right_child(&treelink, 5);
+        let left = treelink.borrow().left.clone().unwrap();
+        left.borrow_mut().add_left_child(&left, 5);
+
+        let dot_path = temp_path("dot_identity_test.dot");
+        let path = dot_path.to_str().unwrap();
+        generate_dotfile(&treelink, path).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        //names are pre-order numbers: the root, its left child, that one's left child, then the right child
+        for number in 0..4 {
+            assert!(text.contains(&format!("\tn{} [label=5];\n", number)));
+        }
+        assert!(text.contains("\tn0->n1;\n"));
+        assert!(text.contains("\tn1->n2;\n"));
+        assert!(text.contains("\tn0->n3;\n"));
+        assert!(!text.contains("5->5"));
+        //the same shape gives the same text, however the nodes were made or copied
+        let copy = Node::new_nodelink(5);
+        copy.borrow_mut().add_right_child(&copy, 5);
+        copy.borrow_mut().left = Some(left.borrow().get_nodelink_copy());
+        let mut output = Vec::new();
+        write_dot(&copy, &mut output, &DotOptions::new()).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), text);
+        let restored = dot_reader::read_dotfile(path).unwrap();
+        assert_eq!(restored, treelink);
+        assert_eq!(Node::preorder_values(&restored), vec![5, 5, 5, 5]);
+        crate::tool::try_generate_dotfile(&treelink, path).unwrap();
+        assert_eq!(std::fs::read_to_string(&dot_path).unwrap(), text);
+        //names are positions: once a node is added before it in pre-order the right child moves up to n4
+        left.borrow_mut().add_right_child(&left, 5);
+        let mut output = Vec::new();
+        write_dot(&treelink, &mut output, &DotOptions::new()).unwrap();
+        let changed = String::from_utf8(output).unwrap();
+        assert!(changed.contains("\tn1->n3;\n") && changed.contains("\tn0->n4;\n"));
+        let _ = std::fs::remove_file(&dot_path);
+    }
+
+    #[test]
+    fn test_dot_writer() {
+        let rootlink = demo_bst();
+        let treelink: NodeLink = Node::new_nodelink(1);
+        treelink.borrow_mut().add_right_child(&treelink, 2);
+        let right = treelink.borrow().right.clone().unwrap();
+        right.borrow_mut().add_left_child(&right, 2);
+        right.borrow_mut().add_right_child(&right, 3);
+        let dot_path = temp_path("dot_writer_test.dot");
+        let path = dot_path.to_str().unwrap();
+        let streamed_bst = |options: &DotOptions| {
+            let mut output = Vec::new();
+            write_dot_bst(&rootlink, &mut output, options).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let streamed_tree = |options: &DotOptions| {
+            let mut output = Vec::new();
+            write_dot(&treelink, &mut output, options).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        //without max_depth the streamed text is exactly what the file exporters write
+        generate_dotfile_bst(&rootlink, path).unwrap();
+        assert_eq!(streamed_bst(&DotOptions::new()), std::fs::read_to_string(&dot_path).unwrap());
+        generate_dotfile_bst_labeled(&rootlink, path).unwrap();
+        assert_eq!(streamed_bst(&DotOptions::new().edge_labels()), std::fs::read_to_string(&dot_path).unwrap());
+        generate_dotfile(&treelink, path).unwrap();
+        assert_eq!(streamed_tree(&DotOptions::new()), std::fs::read_to_string(&dot_path).unwrap());
+        generate_dotfile_labeled(&treelink, path).unwrap();
+        assert_eq!(streamed_tree(&DotOptions::new().edge_labels()), std::fs::read_to_string(&dot_path).unwrap());
+        let styled = DotOptions::new().fill(6, "gold").edge_style(15, 6, "color=red");
+        generate_dotfile_bst_styled(&rootlink, path, &styled).unwrap();
+        assert_eq!(streamed_bst(&styled), std::fs::read_to_string(&dot_path).unwrap());
+        let _ = std::fs::remove_file(&dot_path);
+        //a path that can't be created comes back as an error
+        assert!(generate_dotfile_bst(&rootlink, "/nonexistent_dir/graph.dot").is_err());
+        //a depth past the deepest node changes nothing
+        assert_eq!(streamed_bst(&DotOptions::new().max_depth(4)), streamed_bst(&DotOptions::new()));
+
+        //depth 1 keeps 15, 6 and 18, everything below 6 and 18 becomes one box each
+        let text = streamed_bst(&DotOptions::new().max_depth(1));
+        assert_eq!(
+            text,
+            concat!(
+                "digraph tree{\n\tordering=out;\n\tn0 [label=15];\n\tn1 [label=6];\n\tn0->n1;\n",
+                "\t\"more_n1\" [label=\"… (6 nodes)\", shape=box, style=dashed];\n\tn1->\"more_n1\" [style=dashed];\n",
+                "\tn2 [label=18];\n\tn0->n2;\n",
+                "\t\"more_n2\" [label=\"… (2 nodes)\", shape=box, style=dashed];\n\tn2->\"more_n2\" [style=dashed];\n}"
+            )
+        );
+        //a leaf at the cut (2 is n3) gets no box, 13 (n6) hides 9
+        let text = streamed_bst(&DotOptions::new().max_depth(3));
+        assert!(text.contains("\t\"more_n6\" [label=\"… (1 nodes)\""));
+        assert!(!text.contains("more_n3\"") && !text.contains("label=9"));
+        let text = streamed_tree(&DotOptions::new().max_depth(0));
+        assert!(text.contains("\tn0 [label=1];\n"));
+        assert!(text.contains("… (3 nodes)"));
+        assert!(!text.contains("n1 "));
+
+        //a huge chain stays small, the whole tail is one box
+        const DEPTH: i32 = 100_000;
+        let chain = chain(DEPTH);
+        let mut output = Vec::new();
+        write_dot_bst(&chain, &mut output, &DotOptions::new().max_depth(5)).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text.lines().count(), 3 + 6 + 5 * 3 + 2);
+        assert!(text.contains(&format!("… ({} nodes)", DEPTH - 6)));
+
+        //a failing writer is reported, not panicked on
+        struct Broken;
+        impl std::io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let error = write_dot_bst(&rootlink, &mut Broken, &DotOptions::new()).unwrap_err();
+        assert_eq!(error.to_string(), "disk full");
+    }
+}
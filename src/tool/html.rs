@@ -0,0 +1,238 @@
+use crate::structure::bst::BstNodeLink;
+use crate::tool::svg::{self, Canvas, MARGIN, RADIUS};
+use std::fs;
+use std::io;
+
+/**
+ * Single file HTML viewer for a sequence of BST snapshots, inline SVG and script, nothing loaded from the network
+ * A slider steps through the snapshots, clicking a node collapses / expands its subtree and hovering
+ * shows its depth and subtree size
+ * e.g: let mut viewer = BstViewer::new(); viewer.record("insert 5", &root); ... viewer.write("tree.html")?;
+ * Every snapshot is laid out when it is recorded, so the tree can keep changing afterwards
+ */
+#[derive(Default)]
+pub struct BstViewer {
+    frames: Vec<Frame>,
+}
+
+struct Frame {
+    caption: String,
+    nodes: Vec<FrameNode>,
+}
+
+struct FrameNode {
+    key: String,
+    x: f64,
+    depth: usize,
+    parent: Option<usize>,
+    size: usize,
+}
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 1em; }
+#controls { display: flex; gap: 1em; align-items: center; }
+#step { width: 30em; }
+#info { min-height: 1.5em; margin: 0.5em 0; color: #333; }
+.node { cursor: pointer; }
+.node circle { fill: white; stroke: black; stroke-width: 1.5; }
+.node:hover circle { fill: lightblue; }
+.node.collapsed circle { fill: #ddd; stroke-dasharray: 4 2; }
+.edges line { stroke: black; stroke-width: 1.5; }
+.frame text { font-size: 12px; text-anchor: middle; dominant-baseline: central; }
+";
+
+//a node is hidden when one of its ancestors is collapsed, the edge into a node shares its visibility
+const SCRIPT: &str = "const frames = document.querySelectorAll('.frame');
+const slider = document.getElementById('step');
+const caption = document.getElementById('caption');
+const position = document.getElementById('position');
+const info = document.getElementById('info');
+function show(index) {
+  frames.forEach((frame, other) => frame.hidden = other !== index);
+  caption.textContent = frames[index].dataset.caption;
+  position.textContent = (index + 1) + ' / ' + frames.length;
+  info.textContent = '';
+}
+frames.forEach(frame => {
+  const nodes = frame.querySelectorAll('.node');
+  const byId = {};
+  nodes.forEach(node => byId[node.dataset.id] = node);
+  function refresh() {
+    nodes.forEach(node => {
+      let hidden = false;
+      for (let parent = node.dataset.parent; parent !== ''; parent = byId[parent].dataset.parent) {
+        if (byId[parent].classList.contains('collapsed')) { hidden = true; break; }
+      }
+      node.style.display = hidden ? 'none' : '';
+      const edge = frame.querySelector('line[data-child=\"' + node.dataset.id + '\"]');
+      if (edge) edge.style.display = hidden ? 'none' : '';
+    });
+  }
+  nodes.forEach(node => {
+    node.addEventListener('click', () => {
+      if (node.dataset.size > 1) { node.classList.toggle('collapsed'); refresh(); }
+    });
+    node.addEventListener('mouseenter', () => {
+      info.textContent = 'key ' + node.dataset.key + ', depth ' + node.dataset.depth + ', subtree size ' + node.dataset.size;
+    });
+    node.addEventListener('mouseleave', () => info.textContent = '');
+  });
+});
+slider.addEventListener('input', () => show(Number(slider.value)));
+if (frames.length > 0) show(0);
+";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl BstViewer {
+    pub fn new() -> BstViewer {
+        BstViewer::default()
+    }
+
+    /**
+     * Add the current state of the tree as the next snapshot
+     */
+    pub fn record(&mut self, caption: &str, root: &BstNodeLink) {
+        let placements = svg::layout_bst(root);
+        let parents = svg::parents_bst(root);
+        //children come after their parent, so walking backwards finishes every subtree before its root
+        let mut sizes = vec![1; placements.len()];
+        for index in (0..placements.len()).rev() {
+            if let Some(parent) = parents[index] {
+                sizes[parent] += sizes[index];
+            }
+        }
+        let nodes = placements
+            .into_iter()
+            .zip(parents)
+            .zip(sizes)
+            .map(|((placement, parent), size)| FrameNode {
+                key: placement.label,
+                x: placement.x,
+                depth: placement.depth,
+                parent,
+                size,
+            })
+            .collect();
+        self.frames.push(Frame { caption: caption.to_string(), nodes });
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    fn frame_svg(frame: &Frame) -> String {
+        let canvas = Canvas::new(frame.nodes.iter().map(|node| (node.x, node.depth)));
+        let position = |node: &FrameNode| canvas.position(node.x, node.depth);
+        let mut text = canvas.header();
+        if frame.nodes.is_empty() {
+            text += &format!("<text x=\"{:.0}\" y=\"{:.0}\">(empty)</text>\n", MARGIN, MARGIN);
+        }
+        //edges first so the circles cover their ends
+        text += "<g class=\"edges\">\n";
+        for (index, node) in frame.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                let (x1, y1) = position(&frame.nodes[parent]);
+                let (x2, y2) = position(node);
+                text += &format!(
+                    "<line data-child=\"{}\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>\n",
+                    index, x1, y1, x2, y2
+                );
+            }
+        }
+        text += "</g>\n";
+        for (index, node) in frame.nodes.iter().enumerate() {
+            let (x, y) = position(node);
+            text += &format!(
+                "<g class=\"node\" data-id=\"{}\" data-parent=\"{}\" data-key=\"{}\" data-depth=\"{}\" data-size=\"{}\">",
+                index,
+                node.parent.map_or(String::new(), |parent| parent.to_string()),
+                escape(&node.key),
+                node.depth,
+                node.size
+            );
+            text += &format!(
+                "<title>depth {}, subtree size {}</title><circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.0}\"/><text x=\"{:.1}\" y=\"{:.1}\">{}</text></g>\n",
+                node.depth, node.size, x, y, RADIUS, x, y, escape(&node.key)
+            );
+        }
+        text + "</svg>\n"
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>BST viewer</title>\n<style>\n".to_string();
+        html += STYLE;
+        html += "</style>\n</head>\n<body>\n<div id=\"controls\">\n";
+        html += &format!(
+            "<input type=\"range\" id=\"step\" min=\"0\" max=\"{}\" value=\"0\">\n",
+            self.frames.len().saturating_sub(1)
+        );
+        html += "<span id=\"position\"></span>\n<strong id=\"caption\"></strong>\n</div>\n<div id=\"info\"></div>\n";
+        for frame in &self.frames {
+            html += &format!("<div class=\"frame\" data-caption=\"{}\" hidden>\n", escape(&frame.caption));
+            html += &BstViewer::frame_svg(frame);
+            html += "</div>\n";
+        }
+        html += "<script>\n";
+        html += SCRIPT;
+        html + "</script>\n</body>\n</html>\n"
+    }
+
+    pub fn write(&self, output_path: &str) -> io::Result<()> {
+        fs::write(output_path, self.to_html())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BstViewer;
+    use crate::structure::bst::BstNode;
+    use crate::test_util::{demo_bst, temp_path};
+
+    #[test]
+    fn test_html_viewer() {
+        let rootlink = demo_bst();
+        let mut viewer = BstViewer::new();
+        assert!(viewer.is_empty());
+        viewer.record("initial <tree>", &rootlink);
+        let fifteen = rootlink.borrow().tree_search(&15).unwrap();
+        BstNode::tree_delete(&mut rootlink.clone(), &BstNode::resolve_link(&rootlink, &fifteen));
+        viewer.record("delete 15", &rootlink);
+        //a snapshot doesn't follow later changes of the tree
+        for key in [17, 6, 18, 3, 7, 2, 4, 13, 9, 20] {
+            let node = rootlink.borrow().tree_search(&key).unwrap();
+            BstNode::tree_delete(&mut rootlink.clone(), &BstNode::resolve_link(&rootlink, &node));
+        }
+        viewer.record("empty", &rootlink);
+        assert_eq!(viewer.len(), 3);
+
+        let html = viewer.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        //nothing from the network, the only urls are the svg namespaces
+        assert_eq!(html.matches("http").count(), html.matches("xmlns=\"http://www.w3.org/2000/svg\"").count());
+        assert!(!html.contains("src="));
+        assert!(html.contains("<input type=\"range\" id=\"step\" min=\"0\" max=\"2\" value=\"0\">\n"));
+        assert_eq!(html.matches("<div class=\"frame\"").count(), 3);
+        assert!(html.contains("data-caption=\"initial &lt;tree&gt;\""));
+        //root of the first snapshot, then 9 four levels down as a leaf
+        assert!(html.contains("<g class=\"node\" data-id=\"0\" data-parent=\"\" data-key=\"15\" data-depth=\"0\" data-size=\"11\"><title>depth 0, subtree size 11</title>"));
+        assert!(html.contains("data-key=\"9\" data-depth=\"4\" data-size=\"1\">"));
+        //17 took over the root
+        assert!(html.contains("data-parent=\"\" data-key=\"17\" data-depth=\"0\" data-size=\"10\">"));
+        assert_eq!(html.matches("<line data-child=").count(), 10 + 9);
+        assert!(html.contains(">(empty)</text>"));
+        assert!(html.contains("<script>\n") && html.contains("classList.toggle('collapsed')"));
+
+        let output_path = temp_path("viewer_test.html");
+        let path = output_path.to_str().unwrap();
+        viewer.write(path).unwrap();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), html);
+        let _ = std::fs::remove_file(&output_path);
+        assert!(BstViewer::new().to_html().contains("max=\"0\""));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::{Node, NodeLink};
+use std::rc::Rc;
+
+/**
+ * Index based tree the readers (dot_reader, tree_text) fill in before any link exists
+ * values[i] is the value of node i and children[i] its left and right child, both as indices
+ * Links are only created once the whole shape is known and checked
+ */
+pub(crate) struct TreeLayout {
+    pub values: Vec<i32>,
+    pub children: Vec<[Option<usize>; 2]>,
+    pub root: usize,
+}
+
+impl TreeLayout {
+    /**
+     * Number of nodes reachable from the root
+     * With one parent per node and a single root, anything not reached sits on a cycle
+     */
+    pub fn reachable(&self) -> usize {
+        let mut reached = 0;
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            reached += 1;
+            stack.extend(self.children[index].iter().flatten());
+        }
+        reached
+    }
+
+    //the links are created top down with the pending children on a heap stack, so a long chain doesn't recurse
+    pub fn build_tree(&self) -> NodeLink {
+        let root_link = Node::new_nodelink(self.values[self.root]);
+        let mut stack = vec![(self.root, root_link.clone())];
+        while let Some((index, link)) = stack.pop() {
+            let [left, right] = self.children[index];
+            if let Some(left) = left {
+                let child = Node::new_nodelink(self.values[left]);
+                child.borrow_mut().parent = Some(Rc::downgrade(&link));
+                link.borrow_mut().left = Some(child.clone());
+                stack.push((left, child));
+            }
+            if let Some(right) = right {
+                let child = Node::new_nodelink(self.values[right]);
+                child.borrow_mut().parent = Some(Rc::downgrade(&link));
+                link.borrow_mut().right = Some(child.clone());
+                stack.push((right, child));
+            }
+        }
+        root_link
+    }
+
+    pub fn build_bst(&self) -> BstNodeLink {
+        let root_link = BstNode::new_bst_nodelink(self.values[self.root]);
+        let mut stack = vec![(self.root, root_link.clone())];
+        while let Some((index, link)) = stack.pop() {
+            let [left, right] = self.children[index];
+            if let Some(left) = left {
+                let child = BstNode::new_bst_nodelink(self.values[left]);
+                child.borrow_mut().parent = Some(Rc::downgrade(&link));
+                link.borrow_mut().left = Some(child.clone());
+                stack.push((left, child));
+            }
+            if let Some(right) = right {
+                let child = BstNode::new_bst_nodelink(self.values[right]);
+                child.borrow_mut().parent = Some(Rc::downgrade(&link));
+                link.borrow_mut().right = Some(child.clone());
+                stack.push((right, child));
+            }
+        }
+        root_link
+    }
+}
@@ -1,107 +1,193 @@
+pub mod bench;
+mod cells;
+pub mod diagram;
+pub mod differential;
+pub mod dot_diff;
+pub mod dot_options;
+pub mod dot_reader;
+pub mod dot_writer;
+pub mod html;
+mod layout;
+pub mod rng;
+pub mod pretty;
+pub mod serialize;
+pub mod svg;
+pub mod trace;
+pub mod tree_text;
+
 use crate::structure::tree::NodeLink;
 use crate::structure::bst::BstNodeLink;
+use crate::structure::error::BstError;
+use crate::tool::dot_options::DotOptions;
+use crate::tool::dot_writer::{try_write_dot, try_write_dot_bst, write_dot, write_dot_bst};
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufWriter, Write};
 
 /**
  * @root: root node of the tree in NodeLink Type
  * @output_path: write the graphviz structure to output_path
  * Generate graphviz dot file given a NodeLink, you will traverse from root to all leaves incrementally,
  * as you proceed wrote the progress to dot file
+ * The graph is a digraph with ordering=out, edges are written left child first and a missing child
+ * next to an existing one becomes an invisible placeholder, so graphviz keeps a lone child on its side
+ * Nodes are named n0, n1, ... in pre-order with the value as label, e.g: n1 [label=6];
+ * The names only hold within this one file, the same node can be named differently once the tree has changed
  */
-pub fn generate_dotfile(root: &NodeLink, output_path: &str){
-    let graph_name = " tree";
-    let preamble = "graph".to_owned() + graph_name + "{\n";
-    let epilogue = "}";
-    //pass either left child or right child but not the root
-    let graph_arrangement = node_traversal(root);
-    //traverse the node as usual
-    let final_text = preamble + &graph_arrangement + epilogue;
-    let mut output = File::create(output_path).expect("Failed to create");
-    let _ = output.write_all(final_text.as_bytes());}
+pub fn generate_dotfile(root: &NodeLink, output_path: &str) -> io::Result<()>{
+    write_dotfile(output_path, |output| write_dot(root, output, &DotOptions::default()))
+}
 
 /**
- * We will print string as we traverse, node by node
- * at most a line per node printing, e.g: a--b;
- * traversal mode in BFS
+ * Same as generate_dotfile with an L / R label on every edge
  */
-fn node_traversal(node: &NodeLink) -> String{
-    let mut new_info: String = "".to_string();
-    //we print the child nodes first
-    let left_child = &node.borrow().left;
-    //won't print anything if left child is None
-    new_info += &print_child(&node, left_child.as_ref());
-    let right_child = &node.borrow().right;
-    new_info += &print_child(&node, right_child.as_ref());
-    //now we need to traverse deeper
-    if left_child.is_some(){
-        new_info += &node_traversal(&left_child.as_ref().unwrap());
-    }
-    if right_child.is_some(){
-        new_info += &node_traversal(&right_child.as_ref().unwrap());
-    }
-    return new_info;
+pub fn generate_dotfile_labeled(root: &NodeLink, output_path: &str) -> io::Result<()>{
+    write_dotfile(output_path, |output| write_dot(root, output, &DotOptions::new().edge_labels()))
 }
 
-fn print_child(parent_node: &NodeLink, child_node: Option<&NodeLink>) -> String{
-    let mut new_info = "".to_string();
-    if let Some(child) = child_node {
-        //concat parent
-        new_info += "\t";
-        new_info += &parent_node.borrow().value.to_string();
-        //next_info += node.borrow().parent.unwrap().value;
-        new_info += "--";
-        new_info += &child.borrow().value.to_string();
-        new_info += ";\n";
-    }
-    return new_info;
+const DOT_PREAMBLE: &str = "digraph tree{\n\tordering=out;\n";
+const DOT_EPILOGUE: &str = "}";
+
+fn dot_text(graph_arrangement: &str) -> String {
+    DOT_PREAMBLE.to_owned() + graph_arrangement + DOT_EPILOGUE
 }
 
-pub fn generate_dotfile_bst(root: &BstNodeLink, output_path: &str){
-    let graph_name = " tree";
-    let preamble = "graph".to_owned() + graph_name + "{\n";
-    let epilogue = "}";
-    //pass either left child or right child but not the root
-    let graph_arrangement = node_traversal_bst(root);
-    //traverse the node as usual
-    let final_text = preamble + &graph_arrangement + epilogue;
-    let mut output = File::create(output_path).expect("Failed to create");
-    let _ = output.write_all(final_text.as_bytes());}
-
-fn node_traversal_bst(node: &BstNodeLink) -> String{
-    let mut new_info: String = "".to_string();
-    //we print the child nodes first
-    let left_child = &node.borrow().left;
-    //won't print anything if left child is None
-    new_info += &print_child_bst(&node, left_child.as_ref());
-    let right_child = &node.borrow().right;
-    new_info += &print_child_bst(&node, right_child.as_ref());
-    //now we need to traverse deeper
-    if left_child.is_some(){
-        new_info += &node_traversal_bst(&left_child.as_ref().unwrap());
-    }
-    if right_child.is_some(){
-        new_info += &node_traversal_bst(&right_child.as_ref().unwrap());
-    }
-    return new_info;
+//the graph goes straight from the writer into a buffered file, nothing is built up in memory first
+fn write_dotfile<E, F>(output_path: &str, write: F) -> Result<(), E>
+where
+    E: From<io::Error>,
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), E>,
+{
+    let mut output = BufWriter::new(File::create(output_path)?);
+    write(&mut output)?;
+    output.flush()?;
+    Ok(())
 }
 
-fn print_child_bst(parent_node: &BstNodeLink, child_node: Option<&BstNodeLink>) -> String{
+#[derive(Clone, Copy)]
+enum Side {
+    Left,
+    Right,
+}
+
+/**
+ * One child slot of parent, e.g: n5->n6 [label=R];
+ * An empty slot is skipped unless the other one is taken, then an invisible placeholder keeps its place
+ */
+fn print_child(parent: &str, child: Option<&str>, sibling_exists: bool, side: Side, edge_labels: bool, attributes: Option<&str>) -> String{
+    let side_name = match side {
+        Side::Left => "L",
+        Side::Right => "R",
+    };
     let mut new_info = "".to_string();
-    if let Some(child) = child_node {
-        //concat parent
-        new_info += "\t";
-        new_info += &parent_node.borrow().key.unwrap().to_string();
-        //next_info += node.borrow().parent.unwrap().value;
-        new_info += "--";
-        new_info += &child.borrow().key.unwrap().to_string();
-        new_info += ";\n";
+    match child {
+        Some(child) => {
+            let mut all_attributes: Vec<String> = Vec::new();
+            if edge_labels {
+                all_attributes.push(format!("label={}", side_name));
+            }
+            all_attributes.extend(attributes.map(|attributes| attributes.to_string()));
+            //concat parent
+            new_info += "\t";
+            new_info += parent;
+            new_info += "->";
+            new_info += child;
+            if !all_attributes.is_empty() {
+                new_info += &format!(" [{}]", all_attributes.join(", "));
+            }
+            new_info += ";\n";
+        }
+        None if sibling_exists => {
+            let placeholder = format!("\"nil_{}_{}\"", parent, side_name);
+            new_info += &format!("\t{} [style=invis];\n", placeholder);
+            new_info += &format!("\t{}->{} [style=invis];\n", parent, placeholder);
+        }
+        None => {}
     }
-    return new_info;
+    new_info
+}
+
+pub fn generate_dotfile_bst(root: &BstNodeLink, output_path: &str) -> io::Result<()>{
+    write_dotfile(output_path, |output| write_dot_bst(root, output, &DotOptions::default()))
+}
+
+/**
+ * Same as generate_dotfile_bst with an L / R label on every edge
+ */
+pub fn generate_dotfile_bst_labeled(root: &BstNodeLink, output_path: &str) -> io::Result<()>{
+    write_dotfile(output_path, |output| write_dot_bst(root, output, &DotOptions::new().edge_labels()))
+}
+
+/**
+ * generate_dotfile_bst with fill colors, shapes, labels, edge styles and annotations from options
+ * A styled node gets a statement of its own right before its edges, options for keys not in the tree are ignored
+ */
+pub fn generate_dotfile_bst_styled(root: &BstNodeLink, output_path: &str, options: &DotOptions) -> io::Result<()>{
+    write_dotfile(output_path, |output| write_dot_bst(root, output, options))
+}
+
+/**
+ * Same output as generate_dotfile, but a node that is already borrowed or a failing write
+ * is reported as BstError instead of panicking
+ */
+pub fn try_generate_dotfile(root: &NodeLink, output_path: &str) -> Result<(), BstError> {
+    write_dotfile(output_path, |output| try_write_dot(root, output, &DotOptions::default()))
+}
+
+/**
+ * Same output as generate_dotfile_bst, but a child without key, a node that is already borrowed
+ * or a failing write is reported as BstError instead of panicking
+ */
+pub fn try_generate_dotfile_bst(root: &BstNodeLink, output_path: &str) -> Result<(), BstError> {
+    write_dotfile(output_path, |output| try_write_dot_bst(root, output, &DotOptions::default()))
 }
 
 /*
 pub fn graph_dotfile_string(root: &NodeLink) -> String{
     ""
 }
-*/
\ No newline at end of file
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::{dot_reader, generate_dotfile, generate_dotfile_bst, generate_dotfile_bst_labeled, generate_dotfile_labeled};
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::{demo_bst, temp_path};
+
+    #[test]
+    fn test_dot_sides() {
+        let rootlink = demo_bst();
+        let dot_path = temp_path("dot_sides_test.dot");
+        let path = dot_path.to_str().unwrap();
+        generate_dotfile_bst(&rootlink, path).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        //nodes are numbered in pre-order: 15 6 3 2 4 7 13 9 18 17 20
+        assert!(text.starts_with("digraph tree{\n\tordering=out;\n\tn0 [label=15];\n\tn1 [label=6];\n\tn0->n1;\n"));
+        assert!(text.contains("\tn8 [label=18];\n\tn0->n8;\n"));
+        //13 is the lone right child of 7, a placeholder holds the left slot and comes first
+        assert!(text.contains("\tn6 [label=13];\n\t\"nil_n5_L\" [style=invis];\n\tn5->\"nil_n5_L\" [style=invis];\n\tn5->n6;\n"));
+        assert!(text.contains("\tn6->n7;\n\t\"nil_n6_R\" [style=invis];\n\tn6->\"nil_n6_R\" [style=invis];\n"));
+        assert!(!text.contains("nil_n3_"));
+        assert_eq!(dot_reader::read_dotfile_bst(path).unwrap(), rootlink);
+        generate_dotfile_bst_labeled(&rootlink, path).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        assert!(text.contains("\tn5->n6 [label=R];\n"));
+        assert_eq!(dot_reader::read_dotfile_bst(path).unwrap(), rootlink);
+
+        //a generic tree has no key order, the placeholder (or the label) is what keeps 2 on the right
+        let treelink: NodeLink = Node::new_nodelink(1);
+        treelink.borrow_mut().add_right_child(&treelink, 2);
+        let right = treelink.borrow().right.clone().unwrap();
+        right.borrow_mut().add_left_child(&right, -3);
+        generate_dotfile(&treelink, path).unwrap();
+        let restored = dot_reader::read_dotfile(path).unwrap();
+        assert!(restored.borrow().left.is_none());
+        assert_eq!(restored, treelink);
+        generate_dotfile_labeled(&treelink, path).unwrap();
+        let text = std::fs::read_to_string(&dot_path).unwrap();
+        assert!(text.contains("\tn2 [label=-3];\n\tn1->n2 [label=L];\n\t\"nil_n1_R\" [style=invis];\n"));
+        assert_eq!(dot_reader::read_dotfile(path).unwrap(), treelink);
+        crate::tool::try_generate_dotfile(&treelink, path).unwrap();
+        assert_eq!(dot_reader::read_dotfile(path).unwrap(), treelink);
+        let _ = std::fs::remove_file(&dot_path);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::{Node, NodeLink};
+use crate::tool::cells::{cells_of_bst, cells_of_tree, Cell};
+use std::fmt;
+
+/**
+ * Terminal drawing of a tree, no Graphviz needed
+ * Top-down (default):   Sideways (right subtree on top):
+ *   15                   ┌── 18
+ * ┌─┴──┐                 15
+ * 6    18                └── 6
+ * Every node gets its own columns in key (in-order) order, so a left child is always left of its parent
+ * e.g: println!("{}", PrettyTree::bst(&root).ascii().sideways());
+ * BstNode and tree::Node also implement Display with it, {:#} prints sideways
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+struct Glyphs {
+    horizontal: char,
+    //corner above a left child, above a right child, below the parent (both / only left / only right child)
+    left_corner: char,
+    right_corner: char,
+    junction: char,
+    join_left: char,
+    join_right: char,
+    //sideways connectors
+    upper_branch: &'static str,
+    lower_branch: &'static str,
+    through: &'static str,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    horizontal: '─',
+    left_corner: '┌',
+    right_corner: '┐',
+    junction: '┴',
+    join_left: '┘',
+    join_right: '└',
+    upper_branch: "┌── ",
+    lower_branch: "└── ",
+    through: "│   ",
+};
+
+const ASCII: Glyphs = Glyphs {
+    horizontal: '-',
+    left_corner: '+',
+    right_corner: '+',
+    junction: '+',
+    join_left: '+',
+    join_right: '+',
+    upper_branch: "/-- ",
+    lower_branch: "\\-- ",
+    through: "|   ",
+};
+
+pub struct PrettyTree {
+    cells: Vec<Cell>,
+    charset: Charset,
+    sideways: bool,
+}
+
+impl PrettyTree {
+    pub fn bst(root: &BstNodeLink) -> PrettyTree {
+        PrettyTree::from_bst_node(&root.borrow())
+    }
+
+    pub fn tree(root: &NodeLink) -> PrettyTree {
+        PrettyTree::from_node(&root.borrow())
+    }
+
+    //an empty root (key None) gives an empty drawing
+    fn from_bst_node(root: &BstNode) -> PrettyTree {
+        PrettyTree { cells: cells_of_bst(root), charset: Charset::Unicode, sideways: false }
+    }
+
+    fn from_node(root: &Node) -> PrettyTree {
+        PrettyTree { cells: cells_of_tree(root), charset: Charset::Unicode, sideways: false }
+    }
+
+    //plain ascii for terminals without box-drawing characters
+    pub fn ascii(mut self) -> PrettyTree {
+        self.charset = Charset::Ascii;
+        self
+    }
+
+    pub fn charset(mut self, charset: Charset) -> PrettyTree {
+        self.charset = charset;
+        self
+    }
+
+    //root on the left, right subtree above it, for trees too wide for the terminal
+    pub fn sideways(mut self) -> PrettyTree {
+        self.sideways = true;
+        self
+    }
+
+    fn glyphs(&self) -> &'static Glyphs {
+        match self.charset {
+            Charset::Unicode => &UNICODE,
+            Charset::Ascii => &ASCII,
+        }
+    }
+
+    //cells in in-order sequence, each with its depth
+    fn inorder(&self) -> Vec<(usize, usize)> {
+        let mut order = Vec::with_capacity(self.cells.len());
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        let mut next = (!self.cells.is_empty()).then_some((0, 0));
+        loop {
+            while let Some((index, depth)) = next {
+                stack.push((index, depth));
+                next = self.cells[index].left.map(|left| (left, depth + 1));
+            }
+            let Some((index, depth)) = stack.pop() else {
+                break;
+            };
+            order.push((index, depth));
+            next = self.cells[index].right.map(|right| (right, depth + 1));
+        }
+        order
+    }
+
+    fn render_top_down(&self) -> Vec<String> {
+        let glyphs = self.glyphs();
+        let order = self.inorder();
+        //columns: each label starts where the previous one (in key order) ended, plus a gap
+        let mut start = vec![0; self.cells.len()];
+        let mut depth_of = vec![0; self.cells.len()];
+        let mut width = 0;
+        for &(index, depth) in &order {
+            start[index] = width;
+            depth_of[index] = depth;
+            width += self.cells[index].label.chars().count() + 1;
+        }
+        let center = |index: usize| start[index] + (self.cells[index].label.chars().count() - 1) / 2;
+        let height = order.iter().map(|&(_, depth)| depth + 1).max().unwrap_or(0);
+        let mut grid = vec![vec![' '; width]; height * 2];
+        for (index, cell) in self.cells.iter().enumerate() {
+            let row = depth_of[index] * 2;
+            for (offset, ch) in cell.label.chars().enumerate() {
+                grid[row][start[index] + offset] = ch;
+            }
+            if cell.left.is_none() && cell.right.is_none() {
+                continue;
+            }
+            let line = &mut grid[row + 1];
+            let from = cell.left.map_or(center(index), center);
+            let to = cell.right.map_or(center(index), center);
+            for slot in line.iter_mut().take(to + 1).skip(from) {
+                *slot = glyphs.horizontal;
+            }
+            line[center(index)] = match (cell.left, cell.right) {
+                (Some(_), Some(_)) => glyphs.junction,
+                (Some(_), None) => glyphs.join_left,
+                _ => glyphs.join_right,
+            };
+            if cell.left.is_some() {
+                line[from] = glyphs.left_corner;
+            }
+            if cell.right.is_some() {
+                line[to] = glyphs.right_corner;
+            }
+        }
+        //the last connector row is always blank
+        grid.pop();
+        grid.into_iter().map(|row| row.into_iter().collect::<String>().trim_end().to_string()).collect()
+    }
+
+    fn render_sideways(&self) -> Vec<String> {
+        enum Side {
+            Root,
+            Upper,
+            Lower,
+        }
+        enum Task {
+            Visit(usize, String, Side),
+            Emit(String),
+        }
+        let glyphs = self.glyphs();
+        let mut lines = Vec::new();
+        let mut tasks = Vec::new();
+        if !self.cells.is_empty() {
+            tasks.push(Task::Visit(0, String::new(), Side::Root));
+        }
+        while let Some(task) = tasks.pop() {
+            let (index, prefix, side) = match task {
+                Task::Emit(line) => {
+                    lines.push(line);
+                    continue;
+                }
+                Task::Visit(index, prefix, side) => (index, prefix, side),
+            };
+            let cell = &self.cells[index];
+            let blank = " ".repeat(glyphs.through.chars().count());
+            //a right child's subtree sits above, so the rail only continues on the side facing the parent
+            let (line, upper_prefix, lower_prefix) = match side {
+                Side::Root => (cell.label.clone(), prefix.clone(), prefix),
+                Side::Upper => (
+                    format!("{}{}{}", prefix, glyphs.upper_branch, cell.label),
+                    format!("{}{}", prefix, blank),
+                    format!("{}{}", prefix, glyphs.through),
+                ),
+                Side::Lower => (
+                    format!("{}{}{}", prefix, glyphs.lower_branch, cell.label),
+                    format!("{}{}", prefix, glyphs.through),
+                    format!("{}{}", prefix, blank),
+                ),
+            };
+            //stack: the right subtree is printed first, then the node, then the left subtree
+            if let Some(left) = cell.left {
+                tasks.push(Task::Visit(left, lower_prefix, Side::Lower));
+            }
+            tasks.push(Task::Emit(line));
+            if let Some(right) = cell.right {
+                tasks.push(Task::Visit(right, upper_prefix, Side::Upper));
+            }
+        }
+        lines
+    }
+}
+
+impl fmt::Display for PrettyTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.cells.is_empty() {
+            return writeln!(f, "(empty)");
+        }
+        let lines = if self.sideways { self.render_sideways() } else { self.render_top_down() };
+        for line in lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for BstNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pretty = PrettyTree::from_bst_node(self);
+        if f.alternate() {
+            write!(f, "{}", pretty.sideways())
+        } else {
+            write!(f, "{}", pretty)
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pretty = PrettyTree::from_node(self);
+        if f.alternate() {
+            write!(f, "{}", pretty.sideways())
+        } else {
+            write!(f, "{}", pretty)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Charset, PrettyTree};
+    use crate::structure::bst::BstNode;
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::demo_bst;
+
+    #[test]
+    fn test_pretty() {
+        let rootlink = demo_bst();
+        let expected = [
+            "               15",
+            "      ┌────────┴─────┐",
+            "      6              18",
+            "  ┌───┴─┐         ┌──┴──┐",
+            "  3     7         17    20",
+            "┌─┴─┐   └───┐",
+            "2   4       13",
+            "          ┌─┘",
+            "          9",
+        ];
+        assert_eq!(format!("{}", rootlink.borrow()), expected.join("\n") + "\n");
+        let ascii = PrettyTree::bst(&rootlink).ascii().to_string();
+        assert_eq!(ascii.lines().nth(1), Some("      +--------+-----+"));
+        assert!(ascii.is_ascii());
+        assert_eq!(PrettyTree::bst(&rootlink).charset(Charset::Ascii).to_string(), ascii);
+
+        let sideways = [
+            "    ┌── 20", "┌── 18", "│   └── 17", "15", "│       ┌── 13", "│       │   └── 9", "│   ┌── 7", "└── 6",
+            "    │   ┌── 4", "    └── 3", "        └── 2",
+        ];
+        assert_eq!(format!("{:#}", rootlink.borrow()), sideways.join("\n") + "\n");
+        let ascii_sideways = PrettyTree::bst(&rootlink).ascii().sideways().to_string();
+        assert_eq!(ascii_sideways.lines().nth(2), Some("|   \\-- 17"));
+
+        //a generic tree keeps a lone right child on the right
+        let treelink: NodeLink = Node::new_nodelink(1);
+        treelink.borrow_mut().add_right_child(&treelink, 22);
+        let right = treelink.borrow().right.clone().unwrap();
+        right.borrow_mut().add_left_child(&right, 3);
+        assert_eq!(PrettyTree::tree(&treelink).to_string(), "1\n└───┐\n    22\n  ┌─┘\n  3\n");
+        assert_eq!(format!("{:#}", treelink.borrow()), "┌── 22\n│   └── 3\n1\n");
+        let empty = BstNode::new_bst_nodelink(0);
+        empty.borrow_mut().key = None;
+        assert_eq!(format!("{}", empty.borrow()), "(empty)\n");
+    }
+}
@@ -0,0 +1,471 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::{Node, NodeLink};
+use std::io::{Error, ErrorKind, Result};
+use std::rc::Rc;
+
+/*
+ * JSON layout, every node is an object holding its children, a missing child is null
+ * e.g: {"key":15,"left":{"key":6,"left":null,"right":null},"right":null}
+ * tree::Node names its field value instead of key, and it is never null there
+ * The Weak parent isn't written, it is rebuilt on the way back
+ * A sorted-insert chain nests as deep as the tree is high, far deeper than a recursive serde pass survives,
+ * so the objects are written and read with an explicit stack and serde_json only handles the single tokens
+ */
+const BST_KEY: &str = "key";
+const TREE_KEY: &str = "value";
+
+/**
+ * Flat owned copy of a tree in pre-order, children point at their position in the same Vec
+ * Both formats go through it, so neither writing nor reading recurses
+ */
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    key: Option<i32>,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+//hang the entry at index below parent on the given side
+fn attach(entries: &mut [Entry], parent: Option<(usize, bool)>, index: usize) {
+    match parent {
+        Some((parent, true)) => entries[parent].left = Some(index),
+        Some((parent, false)) => entries[parent].right = Some(index),
+        None => {}
+    }
+}
+
+fn entries_of_bst(root: &BstNodeLink) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    //(node, its parent entry and side), right pushed before left so the left subtree comes first
+    let mut stack: Vec<(BstNodeLink, Option<(usize, bool)>)> = vec![(root.clone(), None)];
+    while let Some((node, parent)) = stack.pop() {
+        let node = node.borrow();
+        let index = entries.len();
+        entries.push(Entry { key: node.key, left: None, right: None });
+        attach(&mut entries, parent, index);
+        stack.extend(node.right.clone().map(|right| (right, Some((index, false)))));
+        stack.extend(node.left.clone().map(|left| (left, Some((index, true)))));
+    }
+    entries
+}
+
+fn entries_of_tree(root: &NodeLink) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut stack: Vec<(NodeLink, Option<(usize, bool)>)> = vec![(root.clone(), None)];
+    while let Some((node, parent)) = stack.pop() {
+        let node = node.borrow();
+        let index = entries.len();
+        entries.push(Entry { key: Some(node.value), left: None, right: None });
+        attach(&mut entries, parent, index);
+        stack.extend(node.right.clone().map(|right| (right, Some((index, false)))));
+        stack.extend(node.left.clone().map(|left| (left, Some((index, true)))));
+    }
+    entries
+}
+
+//entries to links, children are linked to their parent both ways
+fn bst_of_entries(entries: &[Entry]) -> BstNodeLink {
+    let links: Vec<BstNodeLink> = entries
+        .iter()
+        .map(|entry| {
+            let link = BstNode::new_bst_nodelink(0);
+            link.borrow_mut().key = entry.key;
+            link
+        })
+        .collect();
+    for (link, entry) in links.iter().zip(entries) {
+        for (child, is_left) in [(entry.left, true), (entry.right, false)] {
+            if let Some(child) = child {
+                links[child].borrow_mut().parent = Some(Rc::downgrade(link));
+                if is_left {
+                    link.borrow_mut().left = Some(links[child].clone());
+                } else {
+                    link.borrow_mut().right = Some(links[child].clone());
+                }
+            }
+        }
+    }
+    links[0].clone()
+}
+
+fn tree_of_entries(entries: &[Entry]) -> std::result::Result<NodeLink, String> {
+    let mut links: Vec<NodeLink> = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let value = entry.key.ok_or_else(|| format!("tree node {} without a value", index))?;
+        links.push(Node::new_nodelink(value));
+    }
+    for (link, entry) in links.iter().zip(entries) {
+        for (child, is_left) in [(entry.left, true), (entry.right, false)] {
+            if let Some(child) = child {
+                links[child].borrow_mut().parent = Some(Rc::downgrade(link));
+                if is_left {
+                    link.borrow_mut().left = Some(links[child].clone());
+                } else {
+                    link.borrow_mut().right = Some(links[child].clone());
+                }
+            }
+        }
+    }
+    Ok(links[0].clone())
+}
+
+//what is still to be written, a node expands into its own text around its two children
+enum Piece {
+    Text(&'static str),
+    Node(usize),
+}
+
+fn child_piece(child: Option<usize>) -> Piece {
+    child.map_or(Piece::Text("null"), Piece::Node)
+}
+
+//the same text serde_json writes for the nested objects, e.g: {"key":6,"left":null,"right":null}
+fn write_nested(entries: &[Entry], key_name: &str) -> String {
+    let mut json = String::new();
+    let mut pending = vec![Piece::Node(0)];
+    while let Some(piece) = pending.pop() {
+        match piece {
+            Piece::Text(text) => json += text,
+            Piece::Node(index) => {
+                let entry = entries[index];
+                let key = entry.key.map_or("null".to_string(), |key| key.to_string());
+                json += &format!("{{\"{}\":{},\"left\":", key_name, key);
+                pending.push(Piece::Text("}"));
+                pending.push(child_piece(entry.right));
+                pending.push(Piece::Text(",\"right\":"));
+                pending.push(child_piece(entry.left));
+            }
+        }
+    }
+    json
+}
+
+//cursor over JSON text, whitespace between tokens is skipped
+struct JsonReader<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl JsonReader<'_> {
+    fn peek(&mut self) -> Option<u8> {
+        let bytes = self.text.as_bytes();
+        while matches!(bytes.get(self.position), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.position += 1;
+        }
+        bytes.get(self.position).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> std::result::Result<(), String> {
+        match self.eat(byte) {
+            true => Ok(()),
+            false => Err(format!("expected '{}' at {}", byte as char, self.position)),
+        }
+    }
+
+    fn null(&mut self) -> bool {
+        self.peek();
+        let found = self.text[self.position..].starts_with("null");
+        if found {
+            self.position += 4;
+        }
+        found
+    }
+
+    //a field name, escapes are left to serde_json
+    fn string(&mut self) -> std::result::Result<String, String> {
+        self.expect(b'"')?;
+        let start = self.position - 1;
+        let bytes = self.text.as_bytes();
+        loop {
+            match bytes.get(self.position) {
+                Some(b'\\') => self.position += 2,
+                Some(b'"') => break,
+                Some(_) => self.position += 1,
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+        self.position += 1;
+        serde_json::from_str(&self.text[start..self.position]).map_err(|error| error.to_string())
+    }
+
+    //the number token, serde_json decides whether it is a valid i32
+    fn integer(&mut self) -> std::result::Result<i32, String> {
+        self.peek();
+        let start = self.position;
+        let bytes = self.text.as_bytes();
+        while matches!(bytes.get(self.position), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+            self.position += 1;
+        }
+        serde_json::from_str(&self.text[start..self.position])
+            .map_err(|error| format!("invalid number at {}: {}", start, error))
+    }
+}
+
+/**
+ * Read the nested objects into entries without recursion
+ * The objects not closed yet wait on a heap stack, innermost last, each with the fields it already had,
+ * a child object is opened on top and its parent picks up again after the child's closing brace
+ * Missing fields read as null like serde's Option, unknown and repeated fields are rejected
+ */
+fn read_nested(text: &str, key_name: &str) -> std::result::Result<Vec<Entry>, String> {
+    let mut reader = JsonReader { text, position: 0 };
+    let mut entries = vec![Entry::default()];
+    reader.expect(b'{')?;
+    //(entry index, fields seen: [key, left, right])
+    let mut open: Vec<(usize, [bool; 3])> = vec![(0, [false; 3])];
+    while let Some(&(index, seen)) = open.last() {
+        if reader.eat(b'}') {
+            open.pop();
+            continue;
+        }
+        if seen.iter().any(|&seen| seen) {
+            reader.expect(b',')?;
+        }
+        let name = reader.string()?;
+        reader.expect(b':')?;
+        let field = match name.as_str() {
+            "left" => 1,
+            "right" => 2,
+            name if name == key_name => 0,
+            name => return Err(format!("unknown field '{}'", name)),
+        };
+        if seen[field] {
+            return Err(format!("duplicate field '{}'", name));
+        }
+        open.last_mut().unwrap().1[field] = true;
+        if reader.null() {
+            continue;
+        }
+        if field == 0 {
+            entries[index].key = Some(reader.integer()?);
+            continue;
+        }
+        reader.expect(b'{')?;
+        let child = entries.len();
+        entries.push(Entry::default());
+        attach(&mut entries, Some((index, field == 1)), child);
+        open.push((child, [false; 3]));
+    }
+    if reader.peek().is_some() {
+        return Err(format!("trailing characters at {}", reader.position));
+    }
+    Ok(entries)
+}
+
+fn json_error(message: String) -> serde_json::Error {
+    <serde_json::Error as serde::de::Error>::custom(message)
+}
+
+/**
+ * Serialize the whole BST below root into nested JSON
+ */
+pub fn bst_to_json(root: &BstNodeLink) -> String {
+    write_nested(&entries_of_bst(root), BST_KEY)
+}
+
+/**
+ * Rebuild a BST from the JSON written by bst_to_json, parent pointers included
+ */
+pub fn bst_from_json(text: &str) -> serde_json::Result<BstNodeLink> {
+    let entries = read_nested(text, BST_KEY).map_err(json_error)?;
+    Ok(bst_of_entries(&entries))
+}
+
+pub fn tree_to_json(root: &NodeLink) -> String {
+    write_nested(&entries_of_tree(root), TREE_KEY)
+}
+
+pub fn tree_from_json(text: &str) -> serde_json::Result<NodeLink> {
+    let entries = read_nested(text, TREE_KEY).map_err(json_error)?;
+    tree_of_entries(&entries).map_err(json_error)
+}
+
+/*
+ * Compact binary layout, nodes are written in pre-order, each one as
+ * a flag byte (HAS_KEY | HAS_LEFT | HAS_RIGHT) followed by the key as 4 little endian bytes when present
+ * The left subtree follows right after its parent, then the right subtree
+ */
+const HAS_KEY: u8 = 0b001;
+const HAS_LEFT: u8 = 0b010;
+const HAS_RIGHT: u8 = 0b100;
+
+//entries are already in pre-order, so the bytes are just the entries one after another
+fn encode(entries: &[Entry]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(entries.len() * 5);
+    for entry in entries {
+        let mut flags = 0;
+        if entry.key.is_some() {
+            flags |= HAS_KEY;
+        }
+        if entry.left.is_some() {
+            flags |= HAS_LEFT;
+        }
+        if entry.right.is_some() {
+            flags |= HAS_RIGHT;
+        }
+        buffer.push(flags);
+        if let Some(key) = entry.key {
+            buffer.extend_from_slice(&key.to_le_bytes());
+        }
+    }
+    buffer
+}
+
+//cursor over the encoded bytes, every read fails with InvalidData instead of panicking on truncated input
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Decoder<'_> {
+    fn read_flags(&mut self) -> Result<(Option<i32>, bool, bool)> {
+        let flags = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| invalid_data("unexpected end of input"))?;
+        if flags & !(HAS_KEY | HAS_LEFT | HAS_RIGHT) != 0 {
+            return Err(invalid_data("unknown node flags"));
+        }
+        self.position += 1;
+        let mut key = None;
+        if flags & HAS_KEY != 0 {
+            let raw = self
+                .bytes
+                .get(self.position..self.position + 4)
+                .ok_or_else(|| invalid_data("unexpected end of input"))?;
+            key = Some(i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]));
+            self.position += 4;
+        }
+        Ok((key, flags & HAS_LEFT != 0, flags & HAS_RIGHT != 0))
+    }
+
+    /**
+     * Read the whole pre-order sequence into entries without recursion
+     * Child slots announced by the flags wait on a heap stack, left on top, so the next node read fills the
+     * innermost open left slot first, exactly the order the encoder wrote them in
+     */
+    fn decode(&mut self) -> Result<Vec<Entry>> {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut open: Vec<Option<(usize, bool)>> = vec![None];
+        while let Some(parent) = open.pop() {
+            let (key, has_left, has_right) = self.read_flags()?;
+            let index = entries.len();
+            entries.push(Entry { key, left: None, right: None });
+            attach(&mut entries, parent, index);
+            if has_right {
+                open.push(Some((index, false)));
+            }
+            if has_left {
+                open.push(Some((index, true)));
+            }
+        }
+        if self.position != self.bytes.len() {
+            return Err(invalid_data("trailing bytes after the tree"));
+        }
+        Ok(entries)
+    }
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/**
+ * Encode the BST below root in the compact pre-order layout
+ */
+pub fn bst_to_bytes(root: &BstNodeLink) -> Vec<u8> {
+    encode(&entries_of_bst(root))
+}
+
+/**
+ * Decode bytes written by bst_to_bytes, parent pointers are rebuilt
+ */
+pub fn bst_from_bytes(bytes: &[u8]) -> Result<BstNodeLink> {
+    let entries = Decoder { bytes, position: 0 }.decode()?;
+    Ok(bst_of_entries(&entries))
+}
+
+pub fn tree_to_bytes(root: &NodeLink) -> Vec<u8> {
+    encode(&entries_of_tree(root))
+}
+
+pub fn tree_from_bytes(bytes: &[u8]) -> Result<NodeLink> {
+    let entries = Decoder { bytes, position: 0 }.decode()?;
+    tree_of_entries(&entries).map_err(|message| invalid_data(&message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::BstNode;
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::{chain, demo_bst};
+
+    #[test]
+    fn test_serialize() {
+        let rootlink = demo_bst();
+
+        let json = bst_to_json(&rootlink);
+        assert!(json.starts_with(r#"{"key":15,"left":{"key":6,"left":{"key":3,"left":{"key":2,"left":null,"right":null},"#));
+        assert!(json.ends_with(r#""right":{"key":20,"left":null,"right":null}}}"#));
+        let restored = bst_from_json(&json).unwrap();
+        assert_eq!(BstNode::inorder_keys(&restored), BstNode::inorder_keys(&rootlink));
+        assert_eq!(bst_to_json(&restored), json);
+        //parent pointers are rebuilt
+        let nine = restored.borrow().tree_search(&9).unwrap();
+        let nine_parent = BstNode::upgrade_weak_to_strong(nine.borrow().parent.clone()).unwrap();
+        assert_eq!(nine_parent.borrow().key, Some(13));
+
+        let bytes = bst_to_bytes(&rootlink);
+        assert_eq!(bytes.len(), 11 * 5);
+        let restored = bst_from_bytes(&bytes).unwrap();
+        assert_eq!(bst_to_json(&restored), json);
+        assert!(bst_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(bst_from_json(r#"{"key":1,"left":"#).is_err());
+        //whitespace and field order are free, missing children are null, anything else is rejected
+        let spaced = bst_from_json("{ \"right\" : { \"key\" : 9 } ,\n \"key\" : 5 }").unwrap();
+        assert_eq!(BstNode::preorder_keys(&spaced), vec![5, 9]);
+        assert!(bst_from_json(r#"{"key":1,"key":2}"#).is_err());
+        assert!(bst_from_json(r#"{"key":1,"middle":null}"#).is_err());
+        assert!(bst_from_json(r#"{"key":1.5}"#).is_err());
+        assert!(bst_from_json(r#"{"key":1} {}"#).is_err());
+        assert!(bst_from_json(r#"{"key":1,}"#).is_err());
+        assert!(tree_from_json(r#"{"value":null,"left":null,"right":null}"#).is_err());
+        assert!(tree_from_json(r#"{"key":1,"left":null,"right":null}"#).is_err());
+
+        //a sorted-insert chain is far deeper than any nesting limit, and neither direction recurses
+        const DEPTH: i32 = 100_000;
+        let chain = chain(DEPTH);
+        let restored = bst_from_json(&bst_to_json(&chain)).unwrap();
+        assert_eq!(BstNode::height(&restored), DEPTH as usize - 1);
+        let restored = bst_from_bytes(&bst_to_bytes(&chain)).unwrap();
+        assert_eq!(BstNode::inorder_keys(&restored).len(), DEPTH as usize);
+        //a long run of left-only flags is just truncated input
+        assert!(bst_from_bytes(&vec![0b010; 1_000_000]).is_err());
+        assert!(tree_from_bytes(&vec![0b010; 1_000_000]).is_err());
+
+        //tree::Node keeps a lone left child on the left
+        let treelink: NodeLink = Node::new_nodelink(88);
+        treelink.borrow_mut().add_left_child(&treelink, 48);
+        treelink.borrow_mut().add_right_child(&treelink, 888);
+        let right = treelink.borrow().right.clone().unwrap();
+        right.borrow_mut().add_left_child(&right, 84);
+        let json = tree_to_json(&treelink);
+        assert!(json.starts_with(r#"{"value":88,"left":{"value":48,"left":null,"right":null},"right":{"value":888,"#));
+        let restored = tree_from_json(&json).unwrap();
+        assert_eq!(tree_to_json(&restored), json);
+        let restored = tree_from_bytes(&tree_to_bytes(&treelink)).unwrap();
+        let eighty_four = restored.borrow().right.clone().unwrap().borrow().left.clone().unwrap();
+        assert_eq!(eighty_four.borrow().value, 84);
+        let eighty_four_parent = Node::upgrade_weak_to_strong(eighty_four.borrow().parent.clone()).unwrap();
+        assert_eq!(eighty_four_parent.borrow().value, 888);
+    }
+}
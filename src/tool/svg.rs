@@ -0,0 +1,276 @@
+use crate::structure::bst::BstNodeLink;
+use crate::structure::tree::NodeLink;
+use crate::tool::cells::{cells_of_bst, cells_of_tree, parents, Cell};
+use std::fs;
+use std::io;
+
+/**
+ * Standalone SVG export with its own tidy (Reingold–Tilford) layout, no Graphviz needed
+ * Subtrees are laid out bottom-up and pushed apart just far enough that their contours keep
+ * a distance of one unit on every level, a parent sits centered above its children and a lone child
+ * is put half a unit to its own side, so a lone right child really is drawn on the right
+ */
+pub(crate) const RADIUS: f64 = 18.0;
+//pixels per layout unit and per level
+pub(crate) const X_UNIT: f64 = 44.0;
+pub(crate) const Y_UNIT: f64 = 64.0;
+pub(crate) const MARGIN: f64 = 30.0;
+
+/**
+ * Pixel frame around a layout, the viewer draws its snapshots in the same frame as render
+ * e.g: let canvas = Canvas::new(placements.iter().map(|placement| (placement.x, placement.depth)));
+ */
+pub(crate) struct Canvas {
+    min_x: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Canvas {
+    //points are (x in layout units, depth), the root at x 0 is always inside
+    pub(crate) fn new(points: impl Iterator<Item = (f64, usize)>) -> Canvas {
+        let (mut min_x, mut max_x, mut max_depth) = (0.0, 0.0, 0);
+        for (x, depth) in points {
+            min_x = f64::min(min_x, x);
+            max_x = f64::max(max_x, x);
+            max_depth = max_depth.max(depth);
+        }
+        Canvas {
+            min_x,
+            width: (max_x - min_x) * X_UNIT + 2.0 * MARGIN,
+            height: max_depth as f64 * Y_UNIT + 2.0 * MARGIN,
+        }
+    }
+
+    //pixel center of a node
+    pub(crate) fn position(&self, x: f64, depth: usize) -> (f64, f64) {
+        ((x - self.min_x) * X_UNIT + MARGIN, depth as f64 * Y_UNIT + MARGIN)
+    }
+
+    //opening svg tag sized to the canvas
+    pub(crate) fn header(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\">\n",
+            self.width, self.height, self.width, self.height
+        )
+    }
+}
+
+/**
+ * Placed node: label, x in layout units (root at 0) and depth (root at 0)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub label: String,
+    pub x: f64,
+    pub depth: usize,
+}
+
+//per level (min, max) x relative to the subtree root, stored deepest level first so the root level is pushed last
+//the real value of an entry is the stored one plus shift, so moving a whole contour is O(1)
+struct Contour {
+    levels: Vec<(f64, f64)>,
+    shift: f64,
+}
+
+impl Contour {
+    fn level(&self, depth: usize) -> (f64, f64) {
+        let (min, max) = self.levels[self.levels.len() - 1 - depth];
+        (min + self.shift, max + self.shift)
+    }
+
+    fn height(&self) -> usize {
+        self.levels.len()
+    }
+}
+
+//merge two contours hung below a new root, left and right already shifted into place
+fn merge(left: Option<Contour>, right: Option<Contour>) -> Contour {
+    let mut merged = match (left, right) {
+        (None, None) => Contour { levels: Vec::new(), shift: 0.0 },
+        (Some(only), None) | (None, Some(only)) => only,
+        (Some(left), Some(right)) => {
+            //reuse the taller one, fold the shorter one into it
+            let (mut tall, short) = if left.height() >= right.height() { (left, right) } else { (right, left) };
+            let tall_len = tall.levels.len();
+            for depth in 0..short.height() {
+                let (short_min, short_max) = short.level(depth);
+                let entry = &mut tall.levels[tall_len - 1 - depth];
+                entry.0 = entry.0.min(short_min - tall.shift);
+                entry.1 = entry.1.max(short_max - tall.shift);
+            }
+            tall
+        }
+    };
+    merged.levels.push((-merged.shift, -merged.shift));
+    merged
+}
+
+/**
+ * x of every cell relative to the root, computed bottom-up without recursion
+ */
+fn layout(cells: &[Cell]) -> Vec<Placement> {
+    if cells.is_empty() {
+        return Vec::new();
+    }
+    //children always come after their parent in cells, so walking backwards is a valid post-order
+    let mut offset = vec![0.0; cells.len()];
+    let mut contours: Vec<Option<Contour>> = (0..cells.len()).map(|_| None).collect();
+    for index in (0..cells.len()).rev() {
+        let cell = &cells[index];
+        let mut left = cell.left.and_then(|left| contours[left].take());
+        let mut right = cell.right.and_then(|right| contours[right].take());
+        let half_gap = match (&left, &right) {
+            (Some(left), Some(right)) => {
+                let mut gap: f64 = 1.0;
+                for depth in 0..left.height().min(right.height()) {
+                    gap = gap.max(left.level(depth).1 - right.level(depth).0 + 1.0);
+                }
+                gap / 2.0
+            }
+            _ => 0.5,
+        };
+        if let (Some(child), Some(contour)) = (cell.left, left.as_mut()) {
+            offset[child] = -half_gap;
+            contour.shift -= half_gap;
+        }
+        if let (Some(child), Some(contour)) = (cell.right, right.as_mut()) {
+            offset[child] = half_gap;
+            contour.shift += half_gap;
+        }
+        contours[index] = Some(merge(left, right));
+    }
+    //absolute positions top-down, parents come first
+    let mut placements: Vec<Placement> = Vec::with_capacity(cells.len());
+    let parent_of = parents(cells);
+    for (index, cell) in cells.iter().enumerate() {
+        let (x, depth) = match parent_of[index] {
+            Some(parent) => (placements[parent].x + offset[index], placements[parent].depth + 1),
+            None => (0.0, 0),
+        };
+        placements.push(Placement { label: cell.label.clone(), x, depth });
+    }
+    placements
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn render(cells: &[Cell]) -> String {
+    let placements = layout(cells);
+    let canvas = Canvas::new(placements.iter().map(|placement| (placement.x, placement.depth)));
+    let position = |placement: &Placement| canvas.position(placement.x, placement.depth);
+    let mut svg = canvas.header();
+    //edges first so the circles cover their ends
+    svg += "<g stroke=\"black\" stroke-width=\"1.5\">\n";
+    for (index, cell) in cells.iter().enumerate() {
+        let (x1, y1) = position(&placements[index]);
+        for child in [cell.left, cell.right].into_iter().flatten() {
+            let (x2, y2) = position(&placements[child]);
+            svg += &format!("<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>\n", x1, y1, x2, y2);
+        }
+    }
+    svg += "</g>\n";
+    svg += "<g font-family=\"sans-serif\" font-size=\"12\" text-anchor=\"middle\" dominant-baseline=\"central\">\n";
+    for placement in &placements {
+        let (x, y) = position(placement);
+        svg += &format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.0}\" fill=\"white\" stroke=\"black\" stroke-width=\"1.5\"/>\n",
+            x, y, RADIUS
+        );
+        svg += &format!("<text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n", x, y, escape(&placement.label));
+    }
+    svg += "</g>\n</svg>\n";
+    svg
+}
+
+/**
+ * Layout of a BST in layout units, nodes in pre-order (root first, left subtree before right)
+ */
+pub fn layout_bst(root: &BstNodeLink) -> Vec<Placement> {
+    layout(&cells_of_bst(&root.borrow()))
+}
+
+/**
+ * Index of the parent of every entry of layout_bst, None for the root
+ */
+pub fn parents_bst(root: &BstNodeLink) -> Vec<Option<usize>> {
+    parents(&cells_of_bst(&root.borrow()))
+}
+
+pub fn layout_tree(root: &NodeLink) -> Vec<Placement> {
+    layout(&cells_of_tree(&root.borrow()))
+}
+
+pub fn bst_to_svg(root: &BstNodeLink) -> String {
+    render(&cells_of_bst(&root.borrow()))
+}
+
+pub fn tree_to_svg(root: &NodeLink) -> String {
+    render(&cells_of_tree(&root.borrow()))
+}
+
+/**
+ * Same as generate_dotfile_bst but writes an SVG picture, an empty tree gives an empty picture
+ */
+pub fn generate_svgfile_bst(root: &BstNodeLink, output_path: &str) -> io::Result<()> {
+    fs::write(output_path, bst_to_svg(root))
+}
+
+pub fn generate_svgfile(root: &NodeLink, output_path: &str) -> io::Result<()> {
+    fs::write(output_path, tree_to_svg(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::BstNode;
+    use crate::structure::tree::{Node, NodeLink};
+    use crate::test_util::{chain, demo_bst, temp_path};
+
+    #[test]
+    fn test_svg() {
+        let rootlink = demo_bst();
+        let placements = layout_bst(&rootlink);
+        let x_of = |label: &str| placements.iter().find(|placement| placement.label == label).unwrap().x;
+        //children on their own side, parents centered above two children
+        assert_eq!(x_of("15"), 0.0);
+        assert!(x_of("6") < 0.0 && x_of("18") > 0.0);
+        assert!(x_of("13") > x_of("7"), "lone right child is drawn on the right");
+        assert!(x_of("9") < x_of("13"), "lone left child is drawn on the left");
+        assert_eq!(x_of("3"), (x_of("2") + x_of("4")) / 2.0);
+        //nodes on the same level are at least one unit apart
+        for depth in 0..=4 {
+            let mut xs: Vec<f64> = placements.iter().filter(|placement| placement.depth == depth).map(|placement| placement.x).collect();
+            xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!(xs.windows(2).all(|pair| pair[1] - pair[0] >= 1.0 - 1e-9), "level {}", depth);
+        }
+        //subtrees are pushed apart by their contours, not by their roots: 7's right spine vs 18's subtree
+        assert!(x_of("13") < x_of("17"));
+
+        let picture = bst_to_svg(&rootlink);
+        assert!(picture.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert_eq!(picture.matches("<circle").count(), 11);
+        assert_eq!(picture.matches("<line").count(), 10);
+        assert!(picture.contains(">13</text>"));
+        let svg_path = temp_path("svg_test.svg");
+        generate_svgfile_bst(&rootlink, svg_path.to_str().unwrap()).unwrap();
+        assert_eq!(std::fs::read_to_string(&svg_path).unwrap(), picture);
+
+        let treelink: NodeLink = Node::new_nodelink(1);
+        treelink.borrow_mut().add_right_child(&treelink, 2);
+        let placements = layout_tree(&treelink);
+        assert_eq!(placements[1], Placement { label: "2".to_string(), x: 0.5, depth: 1 });
+        generate_svgfile(&treelink, svg_path.to_str().unwrap()).unwrap();
+        assert_eq!(std::fs::read_to_string(&svg_path).unwrap().matches("<circle").count(), 2);
+        let _ = std::fs::remove_file(&svg_path);
+
+        //a deep chain doesn't need the call stack
+        let chain = chain(100_000);
+        assert_eq!(layout_bst(&chain).last().unwrap().x, 49_999.5);
+        let empty = BstNode::new_bst_nodelink(0);
+        empty.borrow_mut().key = None;
+        assert!(!bst_to_svg(&empty).contains("<circle"));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::tool::dot_options::DotOptions;
+use crate::tool::generate_dotfile_bst_styled;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/**
+ * Tracing mode for teaching: the traced operation runs on the real tree and every step of the
+ * algorithm is written as a numbered dot file (e.g: delete_15_step03.dot) through generate_dotfile_bst_styled,
+ * plus an index (delete_15_index.txt) with one narration line per step
+ * gold: the current node, lightblue: nodes already compared on the way,
+ * salmon: nodes being transplanted, red edges: edges changed (bold) or about to go away (dashed)
+ */
+const CURRENT: &str = "gold";
+const COMPARED: &str = "lightblue";
+const MOVED: &str = "salmon";
+const CHANGED_EDGE: &str = "color=red, penwidth=2";
+const LEAVING_EDGE: &str = "color=red, style=dashed";
+
+pub struct TraceStep {
+    pub file: PathBuf,
+    pub narration: String,
+}
+
+struct Tracer {
+    dir: PathBuf,
+    name: String,
+    steps: Vec<TraceStep>,
+}
+
+impl Tracer {
+    fn new(dir: &Path, name: String) -> Tracer {
+        Tracer {
+            dir: dir.to_path_buf(),
+            name,
+            steps: Vec::new(),
+        }
+    }
+
+    fn step(&mut self, root: &BstNodeLink, marks: &DotOptions, narration: String) -> io::Result<()> {
+        let file = self.dir.join(format!("{}_step{:02}.dot", self.name, self.steps.len() + 1));
+        generate_dotfile_bst_styled(root, file.to_str().unwrap(), marks)?;
+        self.steps.push(TraceStep { file, narration });
+        Ok(())
+    }
+
+    fn finish(self) -> io::Result<Vec<TraceStep>> {
+        let mut index = String::new();
+        for (number, step) in self.steps.iter().enumerate() {
+            let file_name = step.file.file_name().unwrap().to_string_lossy();
+            index += &format!("step{:02} {}: {}\n", number + 1, file_name, step.narration);
+        }
+        fs::write(self.dir.join(format!("{}_index.txt", self.name)), index)?;
+        Ok(self.steps)
+    }
+}
+
+//visited keys in lightblue, then the current one in gold
+fn path_marks(visited: &[i32], current: Option<i32>) -> DotOptions {
+    let mut marks = DotOptions::new();
+    for &key in visited {
+        marks = marks.fill(key, COMPARED);
+    }
+    if let Some(key) = current {
+        marks = marks.fill(key, CURRENT);
+    }
+    marks
+}
+
+fn key_of(node: &BstNodeLink) -> i32 {
+    node.borrow().key.unwrap()
+}
+
+fn parent_of(node: &BstNodeLink) -> Option<BstNodeLink> {
+    BstNode::upgrade_weak_to_strong(node.borrow().parent.clone())
+}
+
+//every (parent, child) edge of the tree
+fn edges(root: &BstNodeLink) -> Vec<(i32, i32)> {
+    let mut edges = Vec::new();
+    let mut stack = vec![root.clone()];
+    while let Some(node) = stack.pop() {
+        let node = node.borrow();
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            edges.push((node.key.unwrap(), key_of(child)));
+            stack.push(child.clone());
+        }
+    }
+    edges
+}
+
+enum Descent {
+    Found(BstNodeLink),
+    //the node whose missing child would hold the key, None for an empty tree
+    Missing(Option<BstNodeLink>),
+}
+
+//walk down the way tree_search does, one snapshot per comparison
+fn descend(tracer: &mut Tracer, root: &BstNodeLink, key: i32, visited: &mut Vec<i32>) -> io::Result<Descent> {
+    if root.borrow().key.is_none() {
+        tracer.step(root, &DotOptions::new(), "the tree is empty".to_string())?;
+        return Ok(Descent::Missing(None));
+    }
+    let mut current = root.clone();
+    loop {
+        let current_key = key_of(&current);
+        visited.push(current_key);
+        let marks = path_marks(visited, Some(current_key));
+        if key == current_key {
+            tracer.step(root, &marks, format!("{} equals {}, found", key, current_key))?;
+            return Ok(Descent::Found(current));
+        }
+        let (side, next) = if key < current_key {
+            ("left", current.borrow().left.clone())
+        } else {
+            ("right", current.borrow().right.clone())
+        };
+        let relation = if key < current_key { "less" } else { "greater" };
+        match next {
+            Some(next) => {
+                tracer.step(root, &marks, format!("{} is {} than {}, go {}", key, relation, current_key, side))?;
+                current = next;
+            }
+            None => {
+                tracer.step(
+                    root,
+                    &marks,
+                    format!("{} is {} than {} and there is no {} child, {} is not in the tree", key, relation, current_key, side, key),
+                )?;
+                return Ok(Descent::Missing(Some(current)));
+            }
+        }
+    }
+}
+
+/**
+ * tree_search step by step
+ */
+pub fn trace_search(root: &BstNodeLink, key: i32, dir: &Path) -> io::Result<Vec<TraceStep>> {
+    let mut tracer = Tracer::new(dir, format!("search_{}", key));
+    descend(&mut tracer, root, key, &mut Vec::new())?;
+    tracer.finish()
+}
+
+/**
+ * tree_insert step by step, the key is really inserted
+ */
+pub fn trace_insert(root: &BstNodeLink, key: i32, dir: &Path) -> io::Result<Vec<TraceStep>> {
+    let mut tracer = Tracer::new(dir, format!("insert_{}", key));
+    let mut visited = Vec::new();
+    match descend(&mut tracer, root, key, &mut visited)? {
+        Descent::Found(_) => {
+            let marks = path_marks(&visited, Some(key));
+            tracer.step(root, &marks, format!("{} is already in the tree, nothing is inserted", key))?;
+        }
+        Descent::Missing(None) => {
+            BstNode::tree_insert(root, key);
+            tracer.step(root, &path_marks(&[], Some(key)), format!("{} becomes the root", key))?;
+        }
+        Descent::Missing(Some(parent)) => {
+            BstNode::tree_insert(root, key);
+            let parent_key = key_of(&parent);
+            let side = if key < parent_key { "left" } else { "right" };
+            let mut marks = path_marks(&visited, Some(key));
+            marks = marks.edge_style(parent_key, key, CHANGED_EDGE);
+            tracer.step(root, &marks, format!("hang {} as the {} child of {}", key, side, parent_key))?;
+        }
+    }
+    tracer.finish()
+}
+
+/**
+ * tree_successor step by step, starting from the node holding key
+ */
+pub fn trace_successor(root: &BstNodeLink, key: i32, dir: &Path) -> io::Result<Vec<TraceStep>> {
+    let mut tracer = Tracer::new(dir, format!("successor_{}", key));
+    let found = root.borrow().tree_search(&key);
+    let Some(found) = found else {
+        tracer.step(root, &DotOptions::new(), format!("{} is not in the tree", key))?;
+        return tracer.finish();
+    };
+    let node = BstNode::resolve_link(root, &found);
+    let mut visited = vec![key];
+    let right = node.borrow().right.clone();
+    match right {
+        Some(right) => {
+            tracer.step(
+                root,
+                &path_marks(&visited, Some(key)),
+                format!("{} has a right subtree, the successor is its minimum", key),
+            )?;
+            let mut current = right;
+            loop {
+                let current_key = key_of(&current);
+                visited.push(current_key);
+                let left = current.borrow().left.clone();
+                match left {
+                    Some(left) => {
+                        tracer.step(root, &path_marks(&visited, Some(current_key)), format!("{} has a left child, go left", current_key))?;
+                        current = left;
+                    }
+                    None => {
+                        tracer.step(
+                            root,
+                            &path_marks(&visited, Some(current_key)),
+                            format!("{} has no left child, it is the successor of {}", current_key, key),
+                        )?;
+                        break;
+                    }
+                }
+            }
+        }
+        None => {
+            tracer.step(
+                root,
+                &path_marks(&visited, Some(key)),
+                format!("{} has no right subtree, go up until we come from a left child", key),
+            )?;
+            let mut current = node;
+            loop {
+                let current_key = key_of(&current);
+                let Some(parent) = parent_of(&current) else {
+                    tracer.step(
+                        root,
+                        &path_marks(&visited, Some(current_key)),
+                        format!("{} is the root and we came from the right, {} has no successor", current_key, key),
+                    )?;
+                    break;
+                };
+                let parent_key = key_of(&parent);
+                visited.push(parent_key);
+                let from_left = parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(left, &current));
+                if from_left {
+                    tracer.step(
+                        root,
+                        &path_marks(&visited, Some(parent_key)),
+                        format!("{} is the left child of {}, so {} is the successor of {}", current_key, parent_key, parent_key, key),
+                    )?;
+                    break;
+                }
+                tracer.step(
+                    root,
+                    &path_marks(&visited, Some(parent_key)),
+                    format!("{} is the right child of {}, keep going up", current_key, parent_key),
+                )?;
+                current = parent;
+            }
+        }
+    }
+    tracer.finish()
+}
+
+/**
+ * tree_delete step by step, the key is really deleted
+ * The last snapshot shows the tree after the transplants with every new edge in bold red
+ */
+pub fn trace_delete(root: &BstNodeLink, key: i32, dir: &Path) -> io::Result<Vec<TraceStep>> {
+    let mut tracer = Tracer::new(dir, format!("delete_{}", key));
+    let mut visited = Vec::new();
+    let Descent::Found(z) = descend(&mut tracer, root, key, &mut visited)? else {
+        return tracer.finish();
+    };
+    let before = edges(root);
+    let (left, right) = {
+        let z = z.borrow();
+        (z.left.clone(), z.right.clone())
+    };
+    //edges touching z go away in every case
+    let mut marks = path_marks(&[], Some(key));
+    for edge in before.iter().filter(|(parent, child)| *parent == key || *child == key) {
+        marks = marks.edge_style(edge.0, edge.1, LEAVING_EDGE);
+    }
+    let moved = match (&left, &right) {
+        (None, None) => {
+            tracer.step(root, &marks, format!("{} is a leaf, just cut it off", key))?;
+            None
+        }
+        (None, Some(child)) | (Some(child), None) => {
+            let child_key = key_of(child);
+            let side = if left.is_none() { "left" } else { "right" };
+            marks = marks.fill(child_key, MOVED);
+            tracer.step(
+                root,
+                &marks,
+                format!("{} has no {} child, transplant its only child {} into its place", key, side, child_key),
+            )?;
+            Some(child_key)
+        }
+        (Some(_), Some(right)) => {
+            tracer.step(
+                root,
+                &marks,
+                format!("{} has two children, its successor (minimum of the right subtree) takes its place", key),
+            )?;
+            let mut successor = right.clone();
+            let mut path = vec![key];
+            loop {
+                let successor_key = key_of(&successor);
+                path.push(successor_key);
+                let next = successor.borrow().left.clone();
+                match next {
+                    Some(next) => {
+                        tracer.step(root, &path_marks(&path, Some(successor_key)), format!("{} has a left child, go left", successor_key))?;
+                        successor = next;
+                    }
+                    None => break,
+                }
+            }
+            let successor_key = key_of(&successor);
+            let mut marks = marks.clone().fill(successor_key, MOVED);
+            let narration = if Rc::ptr_eq(&successor, right) {
+                format!("successor {} is the right child of {}, it moves up and takes {}'s left subtree", successor_key, key, key)
+            } else {
+                let successor_right = successor.borrow().right.as_ref().map(key_of);
+                for edge in before.iter().filter(|(_, child)| *child == successor_key) {
+                    marks = marks.edge_style(edge.0, edge.1, LEAVING_EDGE);
+                }
+                match successor_right {
+                    Some(successor_right) => format!(
+                        "successor {}: transplant its right child {} into its place, then {} replaces {}",
+                        successor_key, successor_right, successor_key, key
+                    ),
+                    None => format!("successor {}: cut it off its parent, then {} replaces {}", successor_key, successor_key, key),
+                }
+            };
+            tracer.step(root, &marks, narration)?;
+            Some(successor_key)
+        }
+    };
+    let mut root_mut = root.clone();
+    BstNode::tree_delete(&mut root_mut, &z);
+    let mut marks = path_marks(&[], None);
+    if let Some(moved) = moved {
+        marks = marks.fill(moved, MOVED);
+    }
+    for (parent, child) in edges(root).into_iter().filter(|edge| !before.contains(edge)) {
+        marks = marks.edge_style(parent, child, CHANGED_EDGE);
+    }
+    let narration = match root.borrow().key {
+        None => format!("{} removed, the tree is empty now", key),
+        Some(_) => format!("{} removed, new edges are drawn in bold red", key),
+    };
+    tracer.step(root, &marks, narration)?;
+    tracer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::BstNode;
+    use crate::test_util::{demo_bst, temp_path};
+    use crate::tool::dot_reader;
+
+    #[test]
+    fn test_trace() {
+        let dir = temp_path("trace_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let rootlink = demo_bst();
+        let narration = |steps: &[TraceStep]| steps.iter().map(|step| step.narration.clone()).collect::<Vec<String>>();
+
+        let steps = trace_search(&rootlink, 9, &dir).unwrap();
+        assert_eq!(
+            narration(&steps),
+            vec!["9 is less than 15, go left", "9 is greater than 6, go right", "9 is greater than 7, go right", "9 is less than 13, go left", "9 equals 9, found"]
+        );
+        assert!(steps[3].file.ends_with("search_9_step04.dot"));
+        let step = std::fs::read_to_string(&steps[3].file).unwrap();
+        assert!(step.contains("\tn6 [label=13, style=filled, fillcolor=\"gold\"];"));
+        assert!(step.contains("\tn5 [label=7, style=filled, fillcolor=\"lightblue\"];"));
+        let index = std::fs::read_to_string(dir.join("search_9_index.txt")).unwrap();
+        assert_eq!(index.lines().count(), 5);
+        assert!(index.starts_with("step01 search_9_step01.dot: 9 is less than 15, go left"));
+        assert!(narration(&trace_search(&rootlink, 5, &dir).unwrap()).last().unwrap().ends_with("5 is not in the tree"));
+
+        let steps = trace_successor(&rootlink, 13, &dir).unwrap();
+        assert_eq!(steps.last().unwrap().narration, "6 is the left child of 15, so 15 is the successor of 13");
+        let steps = trace_successor(&rootlink, 6, &dir).unwrap();
+        assert_eq!(steps.last().unwrap().narration, "7 has no left child, it is the successor of 6");
+        let steps = trace_successor(&rootlink, 20, &dir).unwrap();
+        assert!(steps.last().unwrap().narration.contains("20 has no successor"));
+
+        let steps = trace_insert(&rootlink, 10, &dir).unwrap();
+        assert_eq!(steps.last().unwrap().narration, "hang 10 as the right child of 9");
+        assert!(std::fs::read_to_string(&steps.last().unwrap().file).unwrap().contains("\tn7->n8 [color=red, penwidth=2];"));
+        assert!(rootlink.borrow().tree_search(&10).is_some());
+
+        //the root with two children, its successor 17 sits deeper in the right subtree
+        let steps = trace_delete(&rootlink, 15, &dir).unwrap();
+        let last = std::fs::read_to_string(&steps.last().unwrap().file).unwrap();
+        assert!(last.contains("\tn0->n1 [color=red, penwidth=2];"));
+        assert!(last.contains("\tn0 [label=17, style=filled, fillcolor=\"salmon\"];"));
+        assert!(!last.contains("label=15"));
+        assert_eq!(BstNode::inorder_keys(&rootlink), vec![2, 3, 4, 6, 7, 9, 10, 13, 17, 18, 20]);
+        assert_eq!(BstNode::validate(&rootlink), Ok(()));
+        //the marks don't get in the way of reading the snapshot back
+        let restored = dot_reader::read_dotfile_bst(steps.last().unwrap().file.to_str().unwrap()).unwrap();
+        assert_eq!(restored, rootlink);
+        let steps = trace_delete(&rootlink, 13, &dir).unwrap();
+        assert!(steps.iter().any(|step| step.narration.contains("transplant its only child 9")));
+        let steps = trace_delete(&rootlink, 42, &dir).unwrap();
+        assert_eq!(steps.len(), 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::structure::tree::NodeLink;
+use crate::tool::layout::TreeLayout;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+/**
+ * Reader and writer for the plain text format used by prime_t4 and friends
+ * Tree Node {
+ *     P88 -> L48;
+ *     P88 -> R888;
+ *     P888 -> L84;
+ * }
+ * Each statement links a parent (P) to its left (L) or right (R) child, so a lone left child stays on the left
+ * A tree made of a single node is written as a bare "P15;" statement
+ * Nodes are referred to by value, so the values have to be unique
+ */
+pub(crate) const HEADER: &str = "Tree Node";
+
+pub fn tree_to_text(root: &NodeLink) -> String {
+    let mut statements = Vec::new();
+    collect_tree_edges(root, &mut statements);
+    if statements.is_empty() {
+        statements.push(format!("P{};", root.borrow().value));
+    }
+    wrap_statements(&statements)
+}
+
+pub fn bst_to_text(root: &BstNodeLink) -> String {
+    let mut statements = Vec::new();
+    if let Some(key) = root.borrow().key {
+        collect_bst_edges(root, &mut statements);
+        if statements.is_empty() {
+            statements.push(format!("P{};", key));
+        }
+    }
+    wrap_statements(&statements)
+}
+
+pub fn tree_from_text(text: &str) -> Result<NodeLink> {
+    let layout = parse_layout(text)?.ok_or_else(|| invalid_data("tree has no nodes".to_string()))?;
+    Ok(layout.build_tree())
+}
+
+/**
+ * An empty body gives back an empty BST root (key None)
+ * The sides are taken from the file as written, a shape that breaks key order is rejected
+ */
+pub fn bst_from_text(text: &str) -> Result<BstNodeLink> {
+    let root = match parse_layout(text)? {
+        Some(layout) => layout.build_bst(),
+        None => {
+            let empty = BstNode::new_bst_nodelink(0);
+            empty.borrow_mut().key = None;
+            return Ok(empty);
+        }
+    };
+    let keys = BstNode::inorder_keys(&root);
+    if keys.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(invalid_data("tree is not a binary search tree".to_string()));
+    }
+    Ok(root)
+}
+
+pub fn generate_treefile(root: &NodeLink, output_path: &str) -> Result<()> {
+    fs::write(output_path, tree_to_text(root))
+}
+
+pub fn generate_treefile_bst(root: &BstNodeLink, output_path: &str) -> Result<()> {
+    fs::write(output_path, bst_to_text(root))
+}
+
+pub fn read_treefile(input_path: &str) -> Result<NodeLink> {
+    tree_from_text(&fs::read_to_string(input_path)?)
+}
+
+pub fn read_treefile_bst(input_path: &str) -> Result<BstNodeLink> {
+    bst_from_text(&fs::read_to_string(input_path)?)
+}
+
+fn wrap_statements(statements: &[String]) -> String {
+    let mut text = HEADER.to_string() + " {\n";
+    for statement in statements {
+        text += "    ";
+        text += statement;
+        text += "\n";
+    }
+    text + "}\n"
+}
+
+//pre-order, so the file reads top down, pending nodes wait on a heap stack
+fn collect_tree_edges(root: &NodeLink, statements: &mut Vec<String>) {
+    let mut stack = vec![root.clone()];
+    while let Some(node) = stack.pop() {
+        let node = node.borrow();
+        if let Some(left) = &node.left {
+            statements.push(format!("P{} -> L{};", node.value, left.borrow().value));
+        }
+        if let Some(right) = &node.right {
+            statements.push(format!("P{} -> R{};", node.value, right.borrow().value));
+        }
+        stack.extend(node.right.clone());
+        stack.extend(node.left.clone());
+    }
+}
+
+fn collect_bst_edges(root: &BstNodeLink, statements: &mut Vec<String>) {
+    let mut stack = vec![root.clone()];
+    while let Some(node) = stack.pop() {
+        let node = node.borrow();
+        let key = node.key.unwrap();
+        if let Some(left) = &node.left {
+            statements.push(format!("P{} -> L{};", key, left.borrow().key.unwrap()));
+        }
+        if let Some(right) = &node.right {
+            statements.push(format!("P{} -> R{};", key, right.borrow().key.unwrap()));
+        }
+        stack.extend(node.right.clone());
+        stack.extend(node.left.clone());
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+//values in order of first appearance with their left and right child slots, None for an empty body
+fn parse_layout(text: &str) -> Result<Option<TreeLayout>> {
+    let text = text.trim();
+    let body = text
+        .strip_prefix(HEADER)
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix('{'))
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or_else(|| invalid_data(format!("expected \"{} {{ ... }}\"", HEADER)))?;
+
+    let mut layout = TreeLayout {
+        values: Vec::new(),
+        children: Vec::new(),
+        root: 0,
+    };
+    let mut indices: HashMap<i32, usize> = HashMap::new();
+    let mut has_parent: Vec<bool> = Vec::new();
+    for statement in body.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        let (parent, child) = match statement.split_once("->") {
+            Some((parent, child)) => (parent.trim(), Some(child.trim())),
+            None => (statement, None),
+        };
+        let parent_value = parse_reference(parent, &['P'], statement)?.1;
+        let parent = index_of(&mut layout, parent_value, &mut indices, &mut has_parent);
+        let Some(child) = child else {
+            continue;
+        };
+        let (side, child_value) = parse_reference(child, &['L', 'R'], statement)?;
+        let child = index_of(&mut layout, child_value, &mut indices, &mut has_parent);
+        if has_parent[child] || child == parent {
+            return Err(invalid_data(format!("'{}': {} already has a parent", statement, child_value)));
+        }
+        let slot = &mut layout.children[parent][if side == 'L' { 0 } else { 1 }];
+        if slot.is_some() {
+            return Err(invalid_data(format!("'{}': {} already has that child", statement, parent_value)));
+        }
+        *slot = Some(child);
+        has_parent[child] = true;
+    }
+
+    let roots: Vec<usize> = (0..layout.values.len()).filter(|&i| !has_parent[i]).collect();
+    match roots.len() {
+        0 if layout.values.is_empty() => return Ok(None),
+        1 => layout.root = roots[0],
+        _ => return Err(invalid_data(format!("expected exactly one root, found {}", roots.len()))),
+    }
+    if layout.reachable() != layout.values.len() {
+        return Err(invalid_data("tree contains a cycle".to_string()));
+    }
+    Ok(Some(layout))
+}
+
+fn index_of(layout: &mut TreeLayout, value: i32, indices: &mut HashMap<i32, usize>, has_parent: &mut Vec<bool>) -> usize {
+    *indices.entry(value).or_insert_with(|| {
+        layout.values.push(value);
+        layout.children.push([None, None]);
+        has_parent.push(false);
+        layout.values.len() - 1
+    })
+}
+
+//split "P88" / "L48" into its marker and value
+fn parse_reference(reference: &str, markers: &[char], statement: &str) -> Result<(char, i32)> {
+    let mut chars = reference.chars();
+    let marker = chars.next().map(|c| c.to_ascii_uppercase());
+    match marker {
+        Some(marker) if markers.contains(&marker) => chars
+            .as_str()
+            .trim()
+            .parse::<i32>()
+            .map(|value| (marker, value))
+            .map_err(|_| invalid_data(format!("'{}': '{}' is not a number", statement, chars.as_str()))),
+        _ => Err(invalid_data(format!(
+            "'{}': expected one of {:?} in front of '{}'",
+            statement, markers, reference
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::BstNode;
+    use crate::test_util::{demo_bst, temp_path};
+    use crate::tool::serialize;
+
+    #[test]
+    fn test_tree_text() {
+        //the fixture checked into the repo, 888 only has a left child
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/prime_t4");
+        let treelink = read_treefile(fixture).unwrap();
+        assert_eq!(treelink.borrow().value, 88);
+        let right = treelink.borrow().right.clone().unwrap();
+        assert_eq!(right.borrow().value, 888);
+        assert_eq!(right.borrow().left.as_ref().unwrap().borrow().value, 84);
+        assert!(right.borrow().right.is_none());
+        assert_eq!(tree_to_text(&treelink), std::fs::read_to_string(fixture).unwrap());
+
+        //84 sits below 888 but on the wrong side of 88
+        assert!(read_treefile_bst(fixture).is_err());
+
+        let bst = demo_bst();
+        let text_path = temp_path("tree_text_test");
+        let text_path = text_path.to_str().unwrap();
+        generate_treefile_bst(&bst, text_path).unwrap();
+        let restored = read_treefile_bst(text_path).unwrap();
+        assert_eq!(serialize::bst_to_json(&restored), serialize::bst_to_json(&bst));
+        generate_treefile(&treelink, text_path).unwrap();
+        let restored = read_treefile(text_path).unwrap();
+        let _ = std::fs::remove_file(text_path);
+        assert_eq!(serialize::tree_to_json(&restored), serialize::tree_to_json(&treelink));
+
+        //edges can come in any order, a single node and an empty bst have their own forms
+        let text = "Tree Node {\n    P7 -> R9;\n    P5 -> R7;\n    P5 -> L3;\n}\n";
+        assert_eq!(BstNode::inorder_keys(&bst_from_text(text).unwrap()), vec![3, 5, 7, 9]);
+        let single = tree_from_text("Tree Node {\n    P15;\n}\n").unwrap();
+        assert_eq!(tree_to_text(&single), "Tree Node {\n    P15;\n}\n");
+        let empty = bst_from_text("Tree Node {\n}\n").unwrap();
+        assert!(empty.borrow().key.is_none());
+        assert_eq!(bst_to_text(&empty), "Tree Node {\n}\n");
+
+        assert!(bst_from_text("Tree Node { P5 -> R3; }").is_err());
+        assert!(tree_from_text("Tree Node { P5 -> L3; P5 -> L4; }").is_err());
+        assert!(tree_from_text("Tree Node { P5 -> L3; P4 -> R3; }").is_err());
+        assert!(tree_from_text("Tree Node { P5 -> X3; }").is_err());
+        assert!(tree_from_text("graph tree { 5--3; }").is_err());
+    }
+}